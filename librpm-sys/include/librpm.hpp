@@ -49,3 +49,6 @@
 #include <rpm/rpmfi.h> // File information
 #include <rpm/rpmts.h> // Transaction sets
 #include <rpm/rpmte.h> // Transaction elements (packages)
+
+/** Additional header files */
+#include <rpm/rpmarchive.h> // Package payload archives
@@ -0,0 +1,250 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Architecture and operating system compatibility queries
+//!
+//! These are answered from the machine tables in rpmrc, so you will need to
+//! call `librpm::config::read_file(None)` first.
+//!
+//! # Example
+//!
+//! Checking whether installed packages match the configured platform:
+//!
+//! ```
+//! use librpm::{arch::Arch, db};
+//!
+//! librpm::config::read_file(None).unwrap();
+//!
+//! let host = Arch::current().unwrap();
+//!
+//! for package in db::installed_packages() {
+//!     if let Some(arch) = package.arch().map(Arch::from) {
+//!         println!("{}: {}", package, arch.is_compatible_with(&host));
+//!     }
+//! }
+//! ```
+
+use crate::macro_context::MacroContext;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+/// Name of the architecture which means "installable anywhere"
+const NOARCH: &str = "noarch";
+
+/// Name of the macro which defines the build architecture
+const BUILD_ARCH_MACRO: &str = "%{_target_cpu}";
+
+/// Name of the macro which defines the build OS
+const BUILD_OS_MACRO: &str = "%{_target_os}";
+
+/// Machine tables in rpmrc (`RPM_MACHTABLE_*` in librpm)
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MachineTable {
+    /// Architectures packages can be installed on
+    InstallArch = librpm_sys::RPM_MACHTABLE_INSTARCH as isize,
+
+    /// Operating systems packages can be installed on
+    InstallOs = librpm_sys::RPM_MACHTABLE_INSTOS as isize,
+
+    /// Architectures packages can be built for
+    BuildArch = librpm_sys::RPM_MACHTABLE_BUILDARCH as isize,
+
+    /// Operating systems packages can be built for
+    BuildOs = librpm_sys::RPM_MACHTABLE_BUILDOS as isize,
+}
+
+/// Score how well `name` matches the configured platform in the given table
+/// (`rpmMachineScore`).
+///
+/// Lower scores are better matches, with `Some(1)` being an exact match.
+/// Returns `None` if `name` is incompatible with the configured platform.
+pub fn machine_score(table: MachineTable, name: &str) -> Option<u32> {
+    let cstr = CString::new(name).ok()?;
+    let score = unsafe { librpm_sys::rpmMachineScore(table as c_int, cstr.as_ptr()) };
+
+    if score > 0 {
+        Some(score as u32)
+    } else {
+        None
+    }
+}
+
+/// CPU architectures (e.g. `x86_64`, `aarch64`, `noarch`)
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Arch(String);
+
+impl Arch {
+    /// Create an architecture from its name
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Arch(name.into())
+    }
+
+    /// Architecture packages are installed for, i.e. the configured
+    /// platform (`rpmGetArchInfo`)
+    pub fn current() -> Option<Self> {
+        machine_info(librpm_sys::rpmGetArchInfo).map(Arch)
+    }
+
+    /// Architecture packages are built for (i.e. `%{_target_cpu}`)
+    pub fn build() -> Option<Self> {
+        expand_target(BUILD_ARCH_MACRO).map(Arch)
+    }
+
+    /// Name of this architecture
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Is this the architecture-independent `noarch`?
+    pub fn is_noarch(&self) -> bool {
+        self.0 == NOARCH
+    }
+
+    /// Score this architecture against the configured platform.
+    ///
+    /// See `machine_score` for the meaning of the result.
+    pub fn score(&self) -> Option<u32> {
+        machine_score(MachineTable::InstallArch, &self.0)
+    }
+
+    /// Can packages of this architecture be installed on a `host` of the
+    /// given architecture?
+    ///
+    /// rpmrc only knows the compatibility of architectures with the
+    /// configured platform. If `host` is not `Arch::current()`, only an exact
+    /// match or `noarch` are considered compatible.
+    pub fn is_compatible_with(&self, host: &Arch) -> bool {
+        if self == host || self.is_noarch() {
+            return true;
+        }
+
+        match Arch::current() {
+            Some(ref current) if current == host => self.score().is_some(),
+            _ => false,
+        }
+    }
+}
+
+impl AsRef<str> for Arch {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Arch {
+    fn from(name: &str) -> Self {
+        Arch::new(name)
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Operating systems (e.g. `linux`)
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Os(String);
+
+impl Os {
+    /// Create an operating system from its name
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Os(name.into())
+    }
+
+    /// Operating system packages are installed for, i.e. the configured
+    /// platform (`rpmGetOsInfo`)
+    pub fn current() -> Option<Self> {
+        machine_info(librpm_sys::rpmGetOsInfo).map(Os)
+    }
+
+    /// Operating system packages are built for (i.e. `%{_target_os}`)
+    pub fn build() -> Option<Self> {
+        expand_target(BUILD_OS_MACRO).map(Os)
+    }
+
+    /// Name of this operating system
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Score this operating system against the configured platform.
+    ///
+    /// See `machine_score` for the meaning of the result.
+    pub fn score(&self) -> Option<u32> {
+        machine_score(MachineTable::InstallOs, &self.0)
+    }
+
+    /// Can packages for this operating system be installed on the
+    /// configured platform?
+    pub fn is_compatible(&self) -> bool {
+        self.score().is_some()
+    }
+}
+
+impl AsRef<str> for Os {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Os {
+    fn from(name: &str) -> Self {
+        Os::new(name)
+    }
+}
+
+impl fmt::Display for Os {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Obtain the canonical name from `rpmGetArchInfo` or `rpmGetOsInfo`
+fn machine_info(info: unsafe extern "C" fn(*mut *const c_char, *mut c_int)) -> Option<String> {
+    let mut name: *const c_char = ptr::null();
+    let mut num: c_int = 0;
+
+    unsafe {
+        info(&mut name, &mut num);
+    }
+
+    if name.is_null() {
+        return None;
+    }
+
+    let name = unsafe { CStr::from_ptr(name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// Expand a target macro, returning `None` if it is undefined
+fn expand_target(macro_name: &str) -> Option<String> {
+    let expanded = MacroContext::default().expand(macro_name).ok()?;
+
+    if expanded.is_empty() || expanded.starts_with('%') {
+        None
+    } else {
+        Some(expanded)
+    }
+}
@@ -19,6 +19,7 @@
 
 use crate::error::{Error, ErrorKind};
 use crate::internal::GlobalState;
+use crate::logging;
 use crate::macro_context::MacroContext;
 use librpm_sys;
 use std::ffi::CString;
@@ -35,18 +36,18 @@ const DB_PATH_MACRO: &str = "_dbpath";
 ///
 /// Configuration is global to the process.
 pub fn read_file(config_file: Option<&Path>) -> Result<(), Error> {
-    let mut global_state = GlobalState::lock();
+    let global_state = GlobalState::lock();
 
     // Avoid invoking `rpmReadConfigFiles` more than once. This vicariously
     // invokes `rpmInitCrypto` which causes segfaults (NULL struct pointer
     // derefs) if invoked more than once.
-    if global_state.configured {
+    if global_state.configured.get() {
         fail!(ErrorKind::Config, "already configured");
     }
 
-    global_state.configured = true;
+    global_state.configured.set(true);
 
-    let rc = match config_file {
+    let (rc, log) = match config_file {
         Some(path) => {
             if !path.exists() {
                 fail!(ErrorKind::Config, "no such file: {}", path.display())
@@ -61,23 +62,29 @@ pub fn read_file(config_file: Option<&Path>) -> Result<(), Error> {
                 )
             })?;
 
-            unsafe { librpm_sys::rpmReadConfigFiles(cstr.as_ptr(), ptr::null()) }
+            logging::observe(|| unsafe {
+                librpm_sys::rpmReadConfigFiles(cstr.as_ptr(), ptr::null())
+            })
+        }
+        None => {
+            logging::observe(|| unsafe { librpm_sys::rpmReadConfigFiles(ptr::null(), ptr::null()) })
         }
-        None => unsafe { librpm_sys::rpmReadConfigFiles(ptr::null(), ptr::null()) },
     };
 
     if rc != 0 {
-        match config_file {
-            Some(path) => fail!(
+        let err = match config_file {
+            Some(path) => format_err!(
                 ErrorKind::Config,
                 "error reading RPM config from: {}",
                 path.display()
             ),
-            None => fail!(
+            None => format_err!(
                 ErrorKind::Config,
                 "error reading RPM config from default location"
             ),
-        }
+        };
+
+        return Err(err.with_log(log));
     }
 
     Ok(())
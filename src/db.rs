@@ -39,12 +39,38 @@
 //! println!("package version: {}", package.version());
 //! ```
 
+mod watcher;
+
+pub use self::watcher::{watch, Event, Watcher};
+
+use crate::error::{Error, ErrorKind};
+use crate::internal::ds::DependencySet;
+use crate::internal::header::Header;
 use crate::internal::iterator::MatchIterator;
 use crate::internal::tag::DBIndexTag;
-use crate::package::Package;
+use crate::internal::tag::Tag;
+use crate::internal::ts::TransactionSet;
+use crate::internal::GlobalState;
+use crate::logging;
+use crate::macro_context::MacroContext;
+use crate::package::{Dependency, Nevra, Package};
+use std::ffi::CString;
+use std::os::raw::c_int;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
 use streaming_iterator::StreamingIterator;
 
+/// Permissions of newly created database files
+const DB_MODE: c_int = 0o644;
+
+/// Name of the macro which selects the backend for new databases
+const DB_BACKEND_MACRO: &str = "_db_backend";
+
 /// Iterator over the RPM database which returns `Package` structs.
+///
+/// librpm isn't thread-safe, so while an iterator is alive, other threads
+/// using this library wait for it to be dropped. Queries on the same thread,
+/// including nested ones, aren't affected.
 pub struct Iter(MatchIterator);
 
 impl Iterator for Iter {
@@ -52,7 +78,32 @@ impl Iterator for Iter {
 
     /// Obtain the next header from the iterator.
     fn next(&mut self) -> Option<Package> {
-        self.0.next().map(|h| h.to_package())
+        loop {
+            // Skip headers which aren't packages (see `Header::to_package`)
+            if let Ok(mut package) = self.0.next()?.to_package() {
+                package.record = self.0.offset();
+                return Some(package);
+            }
+        }
+    }
+}
+
+impl Iter {
+    /// Iterate over the full headers of the matching packages instead.
+    pub fn headers(self) -> Headers {
+        Headers(self.0)
+    }
+}
+
+/// Iterator over the RPM database which returns `Header` structs.
+pub struct Headers(MatchIterator);
+
+impl Iterator for Headers {
+    type Item = Header;
+
+    /// Obtain the next header from the iterator.
+    fn next(&mut self) -> Option<Header> {
+        self.0.next().cloned()
     }
 }
 
@@ -61,6 +112,10 @@ impl Iterator for Iter {
 pub enum Index {
     /// Search by package name.
     Name,
+
+    /// Search by trigger condition name, i.e. find packages with triggers
+    /// which run when the named package is installed or removed.
+    Triggers,
 }
 
 impl Index {
@@ -75,9 +130,322 @@ pub fn installed_packages() -> Iter {
     Iter(MatchIterator::new(DBIndexTag::PACKAGES, None))
 }
 
+/// Find installed packages by label, i.e. `name-[epoch:]version-release.arch`
+/// where all parts but the name are optional, or the file name of a package
+/// (`.rpm`).
+///
+/// Unlike `Nevra::parse`, this considers every way of splitting the label
+/// (`DBIndexTag::LABEL`), so e.g. `openssl-libs` matches both the package
+/// named "openssl-libs" and version "libs" of a package named "openssl".
+pub fn find_by_label<S: AsRef<str>>(label: S) -> Iter {
+    let label = label.as_ref();
+
+    // File names are always split in full, so are matched exactly
+    if label.ends_with(".rpm") {
+        if let Ok(nevra) = Nevra::parse(label) {
+            let epoch = nevra.epoch().unwrap_or(0);
+            let mut iter = MatchIterator::new(DBIndexTag::NAME, Some(nevra.name()));
+
+            iter.filter_tag(Tag::EPOCH, &epoch.to_string());
+            iter.filter_tag(Tag::VERSION, nevra.version().unwrap_or_default());
+            iter.filter_tag(Tag::RELEASE, nevra.release().unwrap_or_default());
+            iter.filter_tag(Tag::ARCH, nevra.arch().unwrap_or_default());
+
+            return Iter(iter);
+        }
+    }
+
+    // librpm doesn't understand epochs in labels, so filter on them instead
+    let (label, epoch) = split_label_epoch(label);
+    let mut iter = MatchIterator::new(DBIndexTag::LABEL, Some(&label));
+
+    if let Some(epoch) = epoch {
+        iter.filter_tag(Tag::EPOCH, &epoch);
+    }
+
+    Iter(iter)
+}
+
+/// Remove the epoch from a label, returning it separately
+fn split_label_epoch(label: &str) -> (String, Option<String>) {
+    if let Some((left, right)) = label.split_once(':') {
+        let start = left.rfind('-').map(|i| i + 1).unwrap_or(0);
+        let epoch = &left[start..];
+
+        if !epoch.is_empty() && epoch.bytes().all(|b| b.is_ascii_digit()) {
+            // Normalize e.g. "01" to "0" to match the header value
+            let epoch = epoch.trim_start_matches('0');
+            let epoch = if epoch.is_empty() { "0" } else { epoch };
+            return (
+                format!("{}{}", &left[..start], right),
+                Some(epoch.to_owned()),
+            );
+        }
+    }
+
+    (label.to_owned(), None)
+}
+
+/// Find installed packages which provide the given capability.
+///
+/// The capability is given as in spec files, with an optional version range
+/// which is matched against the versions of the provides (e.g. `bash`,
+/// `libfoo >= 2` or `libssl.so.3()(64bit)`). Absolute paths also match the
+/// packages which contain that file.
+pub fn what_provides(capability: &str) -> Result<Vec<Package>, Error> {
+    Dependency::parse(capability).and_then(|dependency| providers(&dependency))
+}
+
+/// Find installed packages which provide the given dependency
+pub(crate) fn providers(dependency: &Dependency) -> Result<Vec<Package>, Error> {
+    let wanted = DependencySet::single(Tag::REQUIRENAME, dependency)?;
+    let mut packages = vec![];
+
+    if dependency.name().starts_with('/') {
+        let files = MatchIterator::new(DBIndexTag::INSTFILENAMES, Some(dependency.name()));
+        collect_matches(files, &mut packages, |_| true);
+    }
+
+    let provides = MatchIterator::new(DBIndexTag::PROVIDENAME, Some(dependency.name()));
+    collect_matches(provides, &mut packages, |header| {
+        DependencySet::from_header(header, Tag::PROVIDENAME).any_overlaps(&wanted)
+    });
+
+    Ok(packages)
+}
+
+/// Find installed packages which require the given capability.
+///
+/// The capability is given as in spec files (see `what_provides`), and
+/// packages match if the version range of their requirement overlaps the
+/// given one. File requirements are matched by path.
+pub fn what_requires(capability: &str) -> Result<Vec<Package>, Error> {
+    let dependency = Dependency::parse(capability)?;
+    let wanted = DependencySet::single(Tag::PROVIDENAME, &dependency)?;
+    let mut packages = vec![];
+
+    let requires = MatchIterator::new(DBIndexTag::REQUIRENAME, Some(dependency.name()));
+    collect_matches(requires, &mut packages, |header| {
+        DependencySet::from_header(header, Tag::REQUIRENAME).any_overlaps(&wanted)
+    });
+
+    Ok(packages)
+}
+
+/// Add the packages from `iter` whose headers satisfy `predicate` to
+/// `packages`, skipping those already in it
+fn collect_matches<F>(mut iter: MatchIterator, packages: &mut Vec<Package>, mut predicate: F)
+where
+    F: FnMut(&Header) -> bool,
+{
+    loop {
+        let package = match iter.next() {
+            Some(header) if predicate(header) => match header.to_package() {
+                Ok(package) => package,
+                Err(_) => continue,
+            },
+            Some(_) => continue,
+            None => break,
+        };
+
+        let record = iter.offset();
+
+        if !packages.iter().any(|p| p.record == record) {
+            packages.push(Package { record, ..package });
+        }
+    }
+}
+
+/// Find the installed package stored under the given database record
+/// number (see `Package::record`).
+pub fn find_by_record(record: u32) -> Option<Package> {
+    Iter(MatchIterator::record(record)).next()
+}
+
+/// Find the installed package with the given header ID, i.e. the hex SHA1 or
+/// SHA256 digest of its header (see `Package::hdrid`).
+///
+/// librpm only indexes SHA1 header digests, so looking up a SHA256 digest
+/// reads every installed header until it finds a match. Prefer the SHA1
+/// digest (`Package::sha1header`) where there's a choice.
+pub fn find_by_hdrid(hdrid: &str) -> Option<Package> {
+    let hdrid = hdrid.to_ascii_lowercase();
+
+    match hdrid.len() {
+        40 => Iter(MatchIterator::new(DBIndexTag::SHA1HEADER, Some(&hdrid))).next(),
+        64 => installed_packages().find(|p| p.sha256header() == Some(hdrid.as_str())),
+        _ => None,
+    }
+}
+
 /// Find installed packages with a search key that exactly matches the given tag.
 ///
 /// Panics if the glob contains null bytes.
 pub fn find<S: AsRef<str>>(index: Index, key: S) -> Iter {
     index.find(key)
 }
+
+/// Maintenance operations on an RPM database.
+///
+/// Each operation acts on the database at the configured `_dbpath` inside
+/// the given root directory, which must be an absolute path (`/` for the
+/// host's own database). Messages logged by librpm while it fails are
+/// attached to the returned `Error`.
+///
+/// # Example
+///
+/// Rebuilding the database of an image mounted at `/mnt/image`:
+///
+/// ```no_run
+/// use librpm::db::Database;
+/// use std::path::Path;
+///
+/// librpm::config::read_file(None).unwrap();
+///
+/// let root = Path::new("/mnt/image");
+/// Database::rebuild(root).unwrap();
+/// Database::verify(root).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Database {
+    _private: (),
+}
+
+impl Database {
+    /// Create a new, empty database under `root` (`rpmtsInitDB`).
+    pub fn init(root: &Path) -> Result<(), Error> {
+        maintain(root, "initializing", |ts| unsafe {
+            librpm_sys::rpmtsInitDB(ts, DB_MODE)
+        })
+    }
+
+    /// Rebuild the database under `root` from its package headers
+    /// (`rpmtsRebuildDB`), like `rpmdb --rebuilddb`.
+    pub fn rebuild(root: &Path) -> Result<(), Error> {
+        maintain(root, "rebuilding", |ts| unsafe {
+            librpm_sys::rpmtsRebuildDB(ts)
+        })
+    }
+
+    /// Check the integrity of the database under `root` (`rpmtsVerifyDB`),
+    /// like `rpmdb --verifydb`.
+    pub fn verify(root: &Path) -> Result<(), Error> {
+        maintain(root, "verifying", |ts| unsafe {
+            librpm_sys::rpmtsVerifyDB(ts)
+        })
+    }
+
+    /// Rebuild the database under `root` using the given backend, like
+    /// `rpmdb --rebuilddb --define '_db_backend <backend>'`.
+    ///
+    /// librpm must have been built with support for both the current and the
+    /// new backend (rpm 4.16 or later for SQLite and NDB).
+    pub fn convert(root: &Path, backend: Backend) -> Result<(), Error> {
+        // Hold the global lock until the backend is restored, so queries on
+        // other threads don't see it. Each step below takes it again.
+        let _global_state = GlobalState::lock();
+        let context = MacroContext::default();
+        context.define(&format!("{} {}", DB_BACKEND_MACRO, backend.name()), 0)?;
+
+        let result = Self::rebuild(root);
+
+        #[cfg(feature = "librpm-4-14")]
+        let restored = context.pop(DB_BACKEND_MACRO);
+
+        #[cfg(not(feature = "librpm-4-14"))]
+        let restored = context.delete(DB_BACKEND_MACRO);
+
+        result.and(restored)
+    }
+}
+
+/// Storage backends of the RPM database
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Backend {
+    /// Berkeley DB (`Packages`), used up to rpm 4.15. Newer versions can
+    /// only read it.
+    BerkeleyDb,
+
+    /// rpm's native format (`Packages.db`), used by openSUSE
+    Ndb,
+
+    /// SQLite (`rpmdb.sqlite`), used by Fedora and Enterprise Linux 9
+    Sqlite,
+}
+
+impl Backend {
+    /// Detect the backend of the database in `db_path` from the files it
+    /// contains, like librpm does when opening it.
+    ///
+    /// Returns `None` if there is no database there.
+    pub fn detect(db_path: &Path) -> Option<Backend> {
+        [Backend::Sqlite, Backend::Ndb, Backend::BerkeleyDb]
+            .iter()
+            .find(|backend| db_path.join(backend.file_name()).is_file())
+            .cloned()
+    }
+
+    /// Name of the backend as understood by the `_db_backend` macro
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::BerkeleyDb => "bdb",
+            Backend::Ndb => "ndb",
+            Backend::Sqlite => "sqlite",
+        }
+    }
+
+    /// Name of the file holding the package headers
+    fn file_name(self) -> &'static str {
+        match self {
+            Backend::BerkeleyDb => "Packages",
+            Backend::Ndb => "Packages.db",
+            Backend::Sqlite => "rpmdb.sqlite",
+        }
+    }
+}
+
+/// Run a database maintenance operation with a transaction set rooted at
+/// `root`, turning a non-zero return code into an `Error`.
+fn maintain<F>(root: &Path, action: &str, op: F) -> Result<(), Error>
+where
+    F: FnOnce(*mut librpm_sys::rpmts_s) -> c_int,
+{
+    let root_cstr = CString::new(root.as_os_str().as_bytes()).map_err(|e| {
+        format_err!(
+            ErrorKind::InvalidInput,
+            "invalid root directory: {} ({})",
+            root.display(),
+            e
+        )
+    })?;
+
+    // Hold the global lock so the configuration (e.g. `_dbpath`) can't
+    // change underneath us, but use a separate transaction set so the global
+    // one keeps its root directory
+    let _global_state = GlobalState::lock();
+    let mut txn = TransactionSet::create();
+    let ts = *txn.as_mut_ptr();
+
+    if unsafe { librpm_sys::rpmtsSetRootDir(ts, root_cstr.as_ptr()) } != 0 {
+        fail!(
+            ErrorKind::InvalidInput,
+            "root directory must be an absolute path: {}",
+            root.display()
+        );
+    }
+
+    let (rc, log) = logging::observe(|| op(ts));
+
+    if rc != 0 {
+        let err = format_err!(
+            ErrorKind::Database,
+            "error {} RPM database in {}",
+            action,
+            root.display()
+        );
+
+        return Err(err.with_log(log));
+    }
+
+    Ok(())
+}
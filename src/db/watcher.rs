@@ -0,0 +1,418 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Watching the RPM database for changes, using inotify
+
+use super::installed_packages;
+use crate::error::{Error, ErrorKind};
+use crate::macro_context::MacroContext;
+use crate::package::Package;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::{CString, OsStr};
+use std::fs::File;
+use std::io;
+use std::mem;
+use std::os::raw::{c_int, c_void};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::time::{Duration, Instant};
+
+/// inotify events which may mean the database was written to
+const WATCH_MASK: u32 = libc::IN_CLOSE_WRITE
+    | libc::IN_MOVED_TO
+    | libc::IN_MOVED_FROM
+    | libc::IN_DELETE
+    | libc::IN_DELETE_SELF
+    | libc::IN_MOVE_SELF;
+
+/// How long the database must be left alone after a change before it's read
+const SETTLE_TIME: Duration = Duration::from_millis(100);
+
+/// Default location of rpm's transaction lock, in the database directory
+const LOCK_FILE: &str = ".rpm.lock";
+
+/// Change to the packages in the RPM database
+///
+/// Packages are compared by header ID, so there is no event for upgrades:
+/// upgrading (or downgrading, or reinstalling a different build of) a
+/// package gives an `Event::Removed` for the old package followed by an
+/// `Event::Added` for the new one.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Event {
+    /// A package was installed
+    Added(Package),
+
+    /// A package was removed
+    Removed(Package),
+
+    /// The database was written to, but the installed packages are exactly
+    /// the same as before (e.g. it was rebuilt, or a transaction failed).
+    /// Only returned for changes which give no other events.
+    Changed,
+}
+
+/// Watches the RPM database for changes (see `watch`)
+#[derive(Debug)]
+pub struct Watcher {
+    /// inotify instance watching the database directory
+    inotify: OwnedFd,
+
+    /// Watch descriptor of the database directory, or `None` once it was
+    /// moved or removed, until it's watched again
+    watch: Option<c_int>,
+
+    /// Database directory
+    dir: PathBuf,
+
+    /// rpm's transaction lock
+    lock_path: PathBuf,
+
+    /// Installed packages as of the last change, by header ID
+    packages: BTreeMap<String, Package>,
+
+    /// Events from the last change not returned yet
+    pending: VecDeque<Event>,
+}
+
+/// Watch the configured RPM database for changes.
+///
+/// The database directory is watched with inotify. Once a transaction has
+/// been committed (i.e. the database is no longer being written to and rpm's
+/// transaction lock is released), the installed packages are compared with
+/// those before by header ID, giving an `Event::Added` or `Event::Removed`
+/// per package. Upgrading a package removes the old one and adds the new one.
+///
+/// The packages are read from whichever database is configured at the time,
+/// so the configuration shouldn't be changed while watching.
+///
+/// If the database directory is replaced (e.g. by `rpmdb --rebuilddb`), the
+/// new directory is watched instead. If it's removed without a replacement,
+/// waiting for the next change fails.
+///
+/// # Example
+///
+/// ```no_run
+/// use librpm::db::{self, Event};
+///
+/// librpm::config::read_file(None).unwrap();
+///
+/// for event in db::watch().unwrap() {
+///     match event.unwrap() {
+///         Event::Added(package) => println!("installed {}", package),
+///         Event::Removed(package) => println!("removed {}", package),
+///         Event::Changed => println!("database changed"),
+///     }
+/// }
+/// ```
+pub fn watch() -> Result<Watcher, Error> {
+    let macros = MacroContext::default();
+    let dir = PathBuf::from(macros.expand("%{_dbpath}")?);
+    let lock_path = match macros.expand("%{?_rpmlock_path}")? {
+        path if path.is_empty() => dir.join(LOCK_FILE),
+        path => PathBuf::from(path),
+    };
+
+    let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+
+    if fd < 0 {
+        return Err(io_error(&dir, io::Error::last_os_error()));
+    }
+
+    let mut watcher = Watcher {
+        inotify: unsafe { OwnedFd::from_raw_fd(fd) },
+        watch: None,
+        dir,
+        lock_path,
+        packages: BTreeMap::new(),
+        pending: VecDeque::new(),
+    };
+
+    watcher.add_watch()?;
+    watcher.packages = snapshot();
+    Ok(watcher)
+}
+
+impl Watcher {
+    /// Directory of the watched database
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Wait for the next change to the database
+    pub fn wait(&mut self) -> Result<Event, Error> {
+        loop {
+            if let Some(event) = self.next_event(None)? {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Wait up to `timeout` for the database to change, returning `None` if
+    /// it didn't.
+    ///
+    /// Once a change begins, it's waited for until committed, even past the
+    /// timeout.
+    pub fn wait_timeout(&mut self, timeout: Duration) -> Result<Option<Event>, Error> {
+        self.next_event(Some(Instant::now() + timeout))
+    }
+
+    /// Return a pending event, or wait until the deadline (if any) for a
+    /// change and compare the packages after it
+    fn next_event(&mut self, deadline: Option<Instant>) -> Result<Option<Event>, Error> {
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(Some(event));
+        }
+
+        // Without a watch, the directory was replaced or removed already
+        if self.watch.is_some() && !self.wait_for_write(deadline)? {
+            return Ok(None);
+        }
+
+        // Let a transaction finish before reading the database
+        while self.wait_for_write(Some(Instant::now() + SETTLE_TIME))? || self.is_locked() {}
+
+        // A directory replacing the watched one is in place by now
+        if self.watch.is_none() {
+            self.add_watch()?;
+        }
+
+        self.compare();
+        Ok(self.pending.pop_front())
+    }
+
+    /// Watch the database directory, replacing any previous watch
+    fn add_watch(&mut self) -> Result<(), Error> {
+        let dir_cstr = CString::new(self.dir.as_os_str().as_bytes()).map_err(|_| {
+            format_err!(
+                ErrorKind::InvalidInput,
+                "database path contains a null byte: {}",
+                self.dir.display()
+            )
+        })?;
+
+        let wd = unsafe {
+            libc::inotify_add_watch(self.inotify.as_raw_fd(), dir_cstr.as_ptr(), WATCH_MASK)
+        };
+
+        if wd < 0 {
+            return Err(io_error(&self.dir, io::Error::last_os_error()));
+        }
+
+        self.watch = Some(wd);
+        Ok(())
+    }
+
+    /// Wait until the deadline (if any) for a write to the database,
+    /// returning false if there was none
+    fn wait_for_write(&mut self, deadline: Option<Instant>) -> Result<bool, Error> {
+        loop {
+            let timeout = match deadline {
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    c_int::try_from(left.as_millis()).unwrap_or(c_int::MAX)
+                }
+                None => -1,
+            };
+
+            let mut pollfd = libc::pollfd {
+                fd: self.inotify.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+
+            match unsafe { libc::poll(&mut pollfd, 1, timeout) } {
+                0 => return Ok(false),
+                n if n < 0 => {
+                    let err = io::Error::last_os_error();
+
+                    if err.kind() != io::ErrorKind::Interrupted {
+                        return Err(io_error(&self.dir, err));
+                    }
+                }
+                _ => {
+                    if self.read_events()? {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Read all queued inotify events, returning whether any of them were
+    /// writes to the database
+    fn read_events(&mut self) -> Result<bool, Error> {
+        let header_len = mem::size_of::<libc::inotify_event>();
+        let mut buffer = [0u8; 4096];
+        let mut written = false;
+
+        loop {
+            let len = unsafe {
+                libc::read(
+                    self.inotify.as_raw_fd(),
+                    buffer.as_mut_ptr() as *mut c_void,
+                    buffer.len(),
+                )
+            };
+
+            if len < 0 {
+                let err = io::Error::last_os_error();
+
+                match err.kind() {
+                    io::ErrorKind::WouldBlock => return Ok(written),
+                    io::ErrorKind::Interrupted => continue,
+                    _ => return Err(io_error(&self.dir, err)),
+                }
+            }
+
+            let events = &buffer[..len as usize];
+            let mut offset = 0;
+
+            while offset + header_len <= events.len() {
+                let event: libc::inotify_event =
+                    unsafe { ptr::read_unaligned(events[offset..].as_ptr() as *const _) };
+                let name_start = offset + header_len;
+                offset = name_start + event.len as usize;
+
+                // Names are padded with null bytes
+                let name = events[name_start..offset.min(events.len())]
+                    .split(|&b| b == 0)
+                    .next()
+                    .unwrap_or_default();
+
+                if Some(event.wd) == self.watch {
+                    // The directory itself was moved away or removed, after
+                    // which the kernel drops the watch (`IN_IGNORED`)
+                    if event.mask & libc::IN_MOVE_SELF != 0 {
+                        unsafe {
+                            libc::inotify_rm_watch(self.inotify.as_raw_fd(), event.wd);
+                        }
+                    }
+
+                    if event.mask & (libc::IN_MOVE_SELF | libc::IN_IGNORED) != 0 {
+                        self.watch = None;
+                        written = true;
+                        continue;
+                    }
+                }
+
+                written |= event.mask & libc::IN_Q_OVERFLOW != 0
+                    || event.mask & libc::IN_DELETE_SELF != 0
+                    || is_database_file(OsStr::from_bytes(name));
+            }
+        }
+    }
+
+    /// Is rpm's transaction lock held by another process?
+    fn is_locked(&self) -> bool {
+        let file = match File::open(&self.lock_path) {
+            Ok(file) => file,
+            Err(_) => return false,
+        };
+
+        let mut lock: libc::flock = unsafe { mem::zeroed() };
+        lock.l_type = libc::F_WRLCK as _;
+        lock.l_whence = libc::SEEK_SET as _;
+
+        let rc = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETLK, &mut lock) };
+        rc == 0 && lock.l_type != libc::F_UNLCK as _
+    }
+
+    /// Compare the installed packages with those before the last change,
+    /// queueing events for the differences
+    fn compare(&mut self) {
+        let packages = snapshot();
+
+        let mut removed: Vec<&Package> = self
+            .packages
+            .iter()
+            .filter(|(id, _)| !packages.contains_key(*id))
+            .map(|(_, package)| package)
+            .collect();
+
+        let mut added: Vec<&Package> = packages
+            .iter()
+            .filter(|(id, _)| !self.packages.contains_key(*id))
+            .map(|(_, package)| package)
+            .collect();
+
+        removed.sort_by_key(|package| package.nevra());
+        added.sort_by_key(|package| package.nevra());
+
+        let events = removed
+            .into_iter()
+            .map(|package| Event::Removed(package.clone()))
+            .chain(
+                added
+                    .into_iter()
+                    .map(|package| Event::Added(package.clone())),
+            );
+
+        self.pending.extend(events);
+
+        if self.pending.is_empty() {
+            self.pending.push_back(Event::Changed);
+        }
+
+        self.packages = packages;
+    }
+}
+
+/// Iterate over the changes to the database, waiting for each
+impl Iterator for Watcher {
+    type Item = Result<Event, Error>;
+
+    fn next(&mut self) -> Option<Result<Event, Error>> {
+        Some(self.wait())
+    }
+}
+
+/// Installed packages by header ID
+fn snapshot() -> BTreeMap<String, Package> {
+    installed_packages()
+        .map(|package| {
+            // Fall back on the package ID or NEVRA for headers without digests
+            let id = package
+                .hdrid()
+                .or_else(|| package.pkgid())
+                .map(str::to_owned)
+                .unwrap_or_else(|| package.nevra());
+
+            (id, package)
+        })
+        .collect()
+}
+
+/// Is the file with the given name in the database directory written to
+/// when the database is, rather than when it's read? (unlike e.g. the
+/// Berkeley DB environment and SQLite's shared memory)
+fn is_database_file(name: &OsStr) -> bool {
+    let name = name.as_bytes();
+
+    !(name.starts_with(b"__db.")
+        || name.ends_with(b"-shm")
+        || name.ends_with(b"-wal")
+        || name.ends_with(b"-journal"))
+}
+
+fn io_error(path: &Path, err: io::Error) -> Error {
+    format_err!(ErrorKind::Io, "{}: {}", path.display(), err).with_source(err)
+}
@@ -0,0 +1,238 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Differences between two sets of packages, e.g. a host and its golden
+//! image, or an RPM database and a directory of package files.
+//!
+//! Packages are matched by name and arch, so multilib packages (e.g.
+//! `glibc.x86_64` and `glibc.i686`) are compared separately.
+//!
+//! # Example
+//!
+//! Comparing the databases of two roots:
+//!
+//! ```no_run
+//! use librpm::{config, db, diff};
+//! use std::path::Path;
+//!
+//! config::read_file(None).unwrap();
+//!
+//! config::set_db_path(Path::new("/srv/golden/var/lib/rpm")).unwrap();
+//! let golden: Vec<_> = db::installed_packages().collect();
+//!
+//! config::set_db_path(Path::new("/var/lib/rpm")).unwrap();
+//! let host: Vec<_> = db::installed_packages().collect();
+//!
+//! for change in diff::diff(golden, host).upgraded() {
+//!     println!("{} -> {}", change.before(), change.after());
+//! }
+//! ```
+
+use crate::error::{Error, ErrorKind};
+use crate::package::Package;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Differences from an old set of packages to a new one
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PackageDiff {
+    added: Vec<Package>,
+    removed: Vec<Package>,
+    upgraded: Vec<Change>,
+    downgraded: Vec<Change>,
+    rebuilt: Vec<Change>,
+}
+
+impl PackageDiff {
+    /// Packages only in the new set
+    pub fn added(&self) -> &[Package] {
+        &self.added
+    }
+
+    /// Packages only in the old set
+    pub fn removed(&self) -> &[Package] {
+        &self.removed
+    }
+
+    /// Packages with a newer EVR in the new set
+    pub fn upgraded(&self) -> &[Change] {
+        &self.upgraded
+    }
+
+    /// Packages with an older EVR in the new set
+    pub fn downgraded(&self) -> &[Change] {
+        &self.downgraded
+    }
+
+    /// Packages with the same EVR in both sets, but different contents (i.e.
+    /// a different package ID)
+    pub fn rebuilt(&self) -> &[Change] {
+        &self.rebuilt
+    }
+
+    /// Are both sets the same?
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.upgraded.is_empty()
+            && self.downgraded.is_empty()
+            && self.rebuilt.is_empty()
+    }
+}
+
+/// A package with the same name and arch in both sets
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Change {
+    before: Package,
+    after: Package,
+}
+
+impl Change {
+    /// The package in the old set
+    pub fn before(&self) -> &Package {
+        &self.before
+    }
+
+    /// The package in the new set
+    pub fn after(&self) -> &Package {
+        &self.after
+    }
+}
+
+/// Compare two sets of packages.
+///
+/// Versions are compared with RPM's ordering (see `Package::compare_evr`).
+/// When several versions of a package are in a set (e.g. kernels), those
+/// with the same EVR on both sides are matched first, and the rest are
+/// paired off from the newest down. Unpaired packages are added or removed.
+pub fn diff<A, B>(old: A, new: B) -> PackageDiff
+where
+    A: IntoIterator<Item = Package>,
+    B: IntoIterator<Item = Package>,
+{
+    let mut new_by_key = by_name_and_arch(new);
+    let mut diff = PackageDiff::default();
+
+    for (key, olds) in by_name_and_arch(old) {
+        let mut news = new_by_key.remove(&key).unwrap_or_default();
+        let mut unmatched = vec![];
+
+        for before in olds {
+            match news
+                .iter()
+                .position(|p| p.compare_evr(&before) == Ordering::Equal)
+            {
+                Some(index) => {
+                    let after = news.remove(index);
+
+                    if is_rebuild(&before, &after) {
+                        diff.rebuilt.push(Change { before, after });
+                    }
+                }
+                None => unmatched.push(before),
+            }
+        }
+
+        unmatched.sort_by(|a, b| b.compare_evr(a));
+        news.sort_by(|a, b| b.compare_evr(a));
+
+        let mut news = news.into_iter();
+
+        for before in unmatched {
+            let after = match news.next() {
+                Some(after) => after,
+                None => {
+                    diff.removed.push(before);
+                    continue;
+                }
+            };
+
+            if after.compare_evr(&before) == Ordering::Less {
+                diff.downgraded.push(Change { before, after });
+            } else {
+                diff.upgraded.push(Change { before, after });
+            }
+        }
+
+        diff.added.extend(news);
+    }
+
+    diff.added.extend(new_by_key.into_values().flatten());
+
+    diff.added.sort_by_key(Package::nevra);
+    diff.removed.sort_by_key(Package::nevra);
+
+    for changes in [&mut diff.upgraded, &mut diff.downgraded, &mut diff.rebuilt] {
+        changes.sort_by_key(|change| change.before.nevra());
+    }
+
+    diff
+}
+
+/// Read all package files (`*.rpm`) in a directory, e.g. to compare a
+/// repository or build output with an RPM database.
+///
+/// Subdirectories are not searched.
+pub fn packages_in_dir<P: AsRef<Path>>(dir: P) -> Result<Vec<Package>, Error> {
+    let dir = dir.as_ref();
+    let io_error =
+        |err: io::Error| format_err!(ErrorKind::Io, "{}: {}", dir.display(), err).with_source(err);
+
+    let mut paths = vec![];
+
+    for entry in fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+
+        if path.extension() == Some(OsStr::new("rpm")) && path.is_file() {
+            paths.push(path);
+        }
+    }
+
+    paths.sort();
+    paths.iter().map(Package::open).collect()
+}
+
+/// Group packages by name and arch
+fn by_name_and_arch<I>(packages: I) -> BTreeMap<(String, Option<String>), Vec<Package>>
+where
+    I: IntoIterator<Item = Package>,
+{
+    let mut groups: BTreeMap<_, Vec<Package>> = BTreeMap::new();
+
+    for package in packages {
+        let key = (package.name().to_owned(), package.arch().map(str::to_owned));
+        groups.entry(key).or_default().push(package);
+    }
+
+    groups
+}
+
+/// Are two packages with the same EVR different builds?
+fn is_rebuild(before: &Package, after: &Package) -> bool {
+    match (before.pkgid(), after.pkgid()) {
+        (Some(before), Some(after)) => before != after,
+        _ => false,
+    }
+}
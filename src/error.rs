@@ -17,6 +17,7 @@
 
 //! librpm error types
 
+use crate::logging::Message;
 use std::fmt::{self, Display};
 
 /// Error type
@@ -27,23 +28,60 @@ pub struct Error {
 
     /// Optional description message
     msg: Option<String>,
+
+    /// Underlying error which caused this one, if any
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+
+    /// Messages librpm logged while the failing call was made
+    log: Vec<Message>,
 }
 
 impl Error {
     /// Create a new error with the given description
     pub fn new(kind: ErrorKind, msg: Option<String>) -> Self {
-        Self { kind, msg }
+        Self {
+            kind,
+            msg,
+            source: None,
+            log: vec![],
+        }
     }
 
     /// Obtain the inner `ErrorKind` for this error
     pub fn kind(&self) -> ErrorKind {
         self.kind
     }
+
+    /// Description of the error, if any
+    pub fn message(&self) -> Option<&str> {
+        self.msg.as_deref()
+    }
+
+    /// Messages librpm logged (via rpmlog) while the failing call was made,
+    /// which often explain the failure in more detail
+    pub fn log(&self) -> &[Message] {
+        &self.log
+    }
+
+    /// Attach the underlying error which caused this one
+    pub(crate) fn with_source<E>(mut self, source: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// Attach messages librpm logged while the failing call was made
+    pub(crate) fn with_log(mut self, log: Vec<Message>) -> Self {
+        self.log = log;
+        self
+    }
 }
 
 impl From<ErrorKind> for Error {
     fn from(kind: ErrorKind) -> Self {
-        Self { kind, msg: None }
+        Self::new(kind, None)
     }
 }
 
@@ -59,19 +97,88 @@ impl Display for Error {
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.source {
+            Some(ref source) => Some(source.as_ref()),
+            None => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::new(ErrorKind::Io, Some(err.to_string())).with_source(err)
+    }
+}
 
 /// Kinds of errors
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum ErrorKind {
     /// Configuration errors
     Config,
+
+    /// Something which was looked up doesn't exist (`RPMRC_NOTFOUND`)
+    NotFound,
+
+    /// Signatures were made with a key which isn't in the keyring
+    /// (`RPMRC_NOKEY`)
+    NoKey,
+
+    /// Signatures were made with a key which isn't trusted
+    /// (`RPMRC_NOTTRUSTED`)
+    NotTrusted,
+
+    /// Signatures or digests don't match the signed data
+    BadSignature,
+
+    /// Errors opening or accessing the RPM database
+    Database,
+
+    /// I/O errors, e.g. reading a package file or writing its contents
+    Io,
+
+    /// Malformed or unreadable package headers
+    Header,
+
+    /// Errors running a transaction
+    Transaction,
+
+    /// Errors defining or expanding macros
+    Macro,
+
+    /// Invalid arguments, e.g. strings containing NUL bytes
+    InvalidInput,
+}
+
+impl ErrorKind {
+    /// Map an `rpmRC` return code to an error kind, using `failure` for
+    /// `RPMRC_FAIL`. Returns `None` for `RPMRC_OK`.
+    pub(crate) fn from_rc(rc: librpm_sys::rpmRC, failure: ErrorKind) -> Option<Self> {
+        match rc {
+            librpm_sys::rpmRC_e_RPMRC_OK => None,
+            librpm_sys::rpmRC_e_RPMRC_NOTFOUND => Some(ErrorKind::NotFound),
+            librpm_sys::rpmRC_e_RPMRC_NOKEY => Some(ErrorKind::NoKey),
+            librpm_sys::rpmRC_e_RPMRC_NOTTRUSTED => Some(ErrorKind::NotTrusted),
+            _ => Some(failure),
+        }
+    }
 }
 
 impl Display for ErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ErrorKind::Config => write!(f, "configuration error"),
+            ErrorKind::NotFound => write!(f, "not found"),
+            ErrorKind::NoKey => write!(f, "public key not available"),
+            ErrorKind::NotTrusted => write!(f, "public key not trusted"),
+            ErrorKind::BadSignature => write!(f, "bad signature"),
+            ErrorKind::Database => write!(f, "database error"),
+            ErrorKind::Io => write!(f, "I/O error"),
+            ErrorKind::Header => write!(f, "header error"),
+            ErrorKind::Transaction => write!(f, "transaction error"),
+            ErrorKind::Macro => write!(f, "macro error"),
+            ErrorKind::InvalidInput => write!(f, "invalid input"),
         }
     }
 }
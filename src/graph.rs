@@ -0,0 +1,374 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Dependency graph of the installed packages
+//!
+//! Each requirement of an installed package is resolved to the installed
+//! packages providing it, in the same way as `db::what_provides`.
+//!
+//! # Example
+//!
+//! Finding the packages nothing else requires:
+//!
+//! ```
+//! use librpm::graph::DependencyGraph;
+//!
+//! librpm::config::read_file(None).unwrap();
+//!
+//! let graph = DependencyGraph::installed();
+//!
+//! for package in graph.leaves() {
+//!     println!("{}", package);
+//! }
+//! ```
+
+use crate::db;
+use crate::internal::{
+    ds::DependencySet,
+    header::Header,
+    iterator::MatchIterator,
+    tag::{DBIndexTag, Tag},
+};
+use crate::package::{Dependency, DependencyFlags, Package, RichDep};
+#[cfg(feature = "serde")]
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt::Write;
+use streaming_iterator::StreamingIterator;
+
+/// Graph of installed packages, with an edge from each package to the
+/// packages providing its requirements
+#[derive(Clone, Debug)]
+pub struct DependencyGraph {
+    /// Packages, sorted by NEVRA
+    packages: Vec<Package>,
+
+    /// Indices of the packages each package requires
+    requires: Vec<BTreeSet<usize>>,
+
+    /// Indices of the packages requiring each package
+    required_by: Vec<BTreeSet<usize>>,
+}
+
+impl DependencyGraph {
+    /// Build the graph of the packages in the configured RPM database.
+    ///
+    /// `rpmlib()` requirements, which are provided by librpm itself, and
+    /// requirements on the package itself are left out. Rich dependencies
+    /// are resolved to the providers of any of the capabilities they may
+    /// require, leaving out conditions (`if`/`unless`) and exclusions
+    /// (`without`).
+    pub fn installed() -> Self {
+        let mut iter = MatchIterator::new(DBIndexTag::PACKAGES, None);
+        let mut entries = vec![];
+
+        while let Some(header) = iter.next() {
+            let package = match header.to_package() {
+                Ok(package) => package,
+                Err(_) => continue,
+            };
+            let requires = requirements(header);
+            let record = iter.offset();
+            entries.push((Package { record, ..package }, requires));
+        }
+
+        entries.sort_by_key(|(package, _)| package.nevra());
+
+        let by_record: HashMap<u32, usize> = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (package, _))| package.record().map(|r| (r, index)))
+            .collect();
+
+        let mut providers: HashMap<Dependency, Vec<usize>> = HashMap::new();
+        let mut requires = vec![BTreeSet::new(); entries.len()];
+        let mut required_by = vec![BTreeSet::new(); entries.len()];
+
+        for (index, (_, dependencies)) in entries.iter().enumerate() {
+            for dependency in dependencies {
+                let provided_by = providers.entry(dependency.clone()).or_insert_with(|| {
+                    // Dependencies read from headers can't contain null
+                    // bytes, which is all that could make this fail
+                    db::providers(dependency)
+                        .unwrap_or_default()
+                        .iter()
+                        .filter_map(|p| p.record().and_then(|r| by_record.get(&r).cloned()))
+                        .collect()
+                });
+
+                for &provider in provided_by.iter() {
+                    if provider != index {
+                        requires[index].insert(provider);
+                        required_by[provider].insert(index);
+                    }
+                }
+            }
+        }
+
+        DependencyGraph {
+            packages: entries.into_iter().map(|(package, _)| package).collect(),
+            requires,
+            required_by,
+        }
+    }
+
+    /// All packages in the graph, sorted by NEVRA
+    pub fn packages(&self) -> &[Package] {
+        &self.packages
+    }
+
+    /// Packages providing the requirements of `package`
+    pub fn requires(&self, package: &Package) -> Vec<&Package> {
+        self.neighbours(package, &self.requires)
+    }
+
+    /// Packages with requirements provided by `package` (reverse dependencies)
+    pub fn required_by(&self, package: &Package) -> Vec<&Package> {
+        self.neighbours(package, &self.required_by)
+    }
+
+    /// Packages which no other package requires
+    pub fn leaves(&self) -> Vec<&Package> {
+        self.required_by
+            .iter()
+            .enumerate()
+            .filter(|(_, required_by)| required_by.is_empty())
+            .map(|(index, _)| &self.packages[index])
+            .collect()
+    }
+
+    /// Packages `package` requires, directly or indirectly
+    pub fn transitive_requires(&self, package: &Package) -> Vec<&Package> {
+        self.closure(package, &self.requires)
+    }
+
+    /// Packages requiring `package`, directly or indirectly, i.e. those which
+    /// would break if it was removed
+    pub fn transitive_required_by(&self, package: &Package) -> Vec<&Package> {
+        self.closure(package, &self.required_by)
+    }
+
+    /// Strongly connected components of the graph: sets of packages which
+    /// all require each other, directly or indirectly.
+    ///
+    /// Every package is in exactly one component, most of them alone.
+    /// Components come after those they require.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<&Package>> {
+        let mut tarjan = Tarjan {
+            edges: &self.requires,
+            next_index: 0,
+            index: vec![None; self.packages.len()],
+            lowlink: vec![0; self.packages.len()],
+            on_stack: vec![false; self.packages.len()],
+            stack: vec![],
+            components: vec![],
+        };
+
+        for node in 0..self.packages.len() {
+            if tarjan.index[node].is_none() {
+                tarjan.visit(node);
+            }
+        }
+
+        tarjan
+            .components
+            .into_iter()
+            .map(|component| component.into_iter().map(|i| &self.packages[i]).collect())
+            .collect()
+    }
+
+    /// Export the graph in the DOT format of Graphviz, with packages named
+    /// by NEVRA
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph packages {\n");
+
+        for (index, package) in self.packages.iter().enumerate() {
+            let from = dot_id(package);
+            writeln!(dot, "    {};", from).unwrap();
+
+            for &provider in &self.requires[index] {
+                writeln!(dot, "    {} -> {};", from, dot_id(&self.packages[provider])).unwrap();
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Index of a package in the graph
+    fn position(&self, package: &Package) -> Option<usize> {
+        self.packages.iter().position(|p| p == package)
+    }
+
+    /// Packages adjacent to `package` along `edges`
+    fn neighbours(&self, package: &Package, edges: &[BTreeSet<usize>]) -> Vec<&Package> {
+        match self.position(package) {
+            Some(index) => edges[index].iter().map(|&i| &self.packages[i]).collect(),
+            None => vec![],
+        }
+    }
+
+    /// Packages reachable from `package` along `edges`, excluding itself
+    fn closure(&self, package: &Package, edges: &[BTreeSet<usize>]) -> Vec<&Package> {
+        let start = match self.position(package) {
+            Some(index) => index,
+            None => return vec![],
+        };
+
+        let mut seen = BTreeSet::new();
+        let mut queue: VecDeque<usize> = edges[start].iter().cloned().collect();
+
+        while let Some(index) = queue.pop_front() {
+            if index != start && seen.insert(index) {
+                queue.extend(edges[index].iter().cloned());
+            }
+        }
+
+        seen.into_iter().map(|i| &self.packages[i]).collect()
+    }
+}
+
+/// Serialized as `{"packages": [NEVRA, ...], "edges": [[from, to], ...]}`,
+/// where `from` requires `to` and both are indices into `packages`
+#[cfg(feature = "serde")]
+impl Serialize for DependencyGraph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let packages: Vec<String> = self.packages.iter().map(Package::nevra).collect();
+        let edges: Vec<(usize, usize)> = self
+            .requires
+            .iter()
+            .enumerate()
+            .flat_map(|(from, to)| to.iter().map(move |&to| (from, to)))
+            .collect();
+
+        let mut state = serializer.serialize_struct("DependencyGraph", 2)?;
+        state.serialize_field("packages", &packages)?;
+        state.serialize_field("edges", &edges)?;
+        state.end()
+    }
+}
+
+/// State of Tarjan's strongly connected components algorithm
+struct Tarjan<'a> {
+    edges: &'a [BTreeSet<usize>],
+    next_index: usize,
+    index: Vec<Option<usize>>,
+    lowlink: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, node: usize) {
+        self.index[node] = Some(self.next_index);
+        self.lowlink[node] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+
+        for &next in &self.edges[node] {
+            match self.index[next] {
+                None => {
+                    self.visit(next);
+                    self.lowlink[node] = self.lowlink[node].min(self.lowlink[next]);
+                }
+                Some(index) if self.on_stack[next] => {
+                    self.lowlink[node] = self.lowlink[node].min(index);
+                }
+                Some(_) => (),
+            }
+        }
+
+        if Some(self.lowlink[node]) == self.index[node] {
+            let mut component = vec![];
+
+            while let Some(member) = self.stack.pop() {
+                self.on_stack[member] = false;
+                component.push(member);
+
+                if member == node {
+                    break;
+                }
+            }
+
+            component.sort_unstable();
+            self.components.push(component);
+        }
+    }
+}
+
+/// Requirements of the package with the given header which can be resolved
+/// to other packages, with rich dependencies flattened
+fn requirements(header: &Header) -> Vec<Dependency> {
+    let mut ds = DependencySet::from_header(header, Tag::REQUIRENAME);
+    let mut requires = vec![];
+
+    while ds.advance() {
+        let dependency = match ds.dependency() {
+            Some(dependency) => dependency,
+            None => continue,
+        };
+
+        if dependency.flags().contains(DependencyFlags::RPMLIB) {
+            continue;
+        }
+
+        if dependency.name().starts_with('(') {
+            if let Ok(rich) = RichDep::parse(dependency.name()) {
+                flatten(&rich, &mut requires);
+            }
+        } else {
+            requires.push(dependency);
+        }
+    }
+
+    requires
+}
+
+/// Collect the simple dependencies in a rich dependency which may have to be
+/// provided: not those in conditions (`if`/`unless`), which only decide
+/// what's required, or in exclusions (`without`)
+fn flatten(rich: &RichDep, out: &mut Vec<Dependency>) {
+    match rich {
+        RichDep::Single(dependency) => out.push(dependency.clone()),
+        RichDep::And(operands) | RichDep::Or(operands) | RichDep::With(operands) => {
+            operands.iter().for_each(|o| flatten(o, out))
+        }
+        RichDep::Without(required, _) => flatten(required, out),
+        RichDep::If {
+            then, otherwise, ..
+        }
+        | RichDep::Unless {
+            then, otherwise, ..
+        } => {
+            flatten(then, out);
+
+            if let Some(otherwise) = otherwise {
+                flatten(otherwise, out);
+            }
+        }
+    }
+}
+
+/// Quoted identifier of a package in DOT
+fn dot_id(package: &Package) -> String {
+    format!(
+        "\"{}\"",
+        package.nevra().replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
@@ -0,0 +1,48 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! RPM package headers: the raw tags and data which describe packages
+//!
+//! `Package` is a convenient view of the most commonly used tags, whereas
+//! `Header` gives access to all of them. With the `serde` feature enabled,
+//! a `Header` serializes as a map of tag names to their data.
+//!
+//! Package files additionally carry a `SignatureHeader`, which holds the
+//! sizes, digests and OpenPGP signatures of the main header and payload.
+//!
+//! # Example
+//!
+//! Dumping the full header of the "rpm-devel" RPM:
+//!
+//! ```
+//! use librpm::Index;
+//!
+//! librpm::config::read_file(None).unwrap();
+//!
+//! let header = Index::Name.find("rpm-devel").headers().next().unwrap();
+//!
+//! for tag in [librpm::header::Tag::NAME, librpm::header::Tag::URL] {
+//!     println!("{}: {:?}", tag.name(), header.get(tag));
+//! }
+//! ```
+
+pub use crate::internal::{
+    header::Header,
+    signature::{Signature, SignatureHeader},
+    tag::{SignatureTag, Tag, TagType},
+    td::TagData,
+};
@@ -0,0 +1,137 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Installation history: installed packages grouped by the transaction which
+//! installed them (`INSTALLTID`).
+//!
+//! Only packages which are still installed are known to the RPM database, so
+//! transactions which removed packages, or whose packages have all been
+//! removed or upgraded since, are not part of the history.
+//!
+//! # Example
+//!
+//! Listing what was installed in the last day:
+//!
+//! ```
+//! use librpm::history;
+//! use std::time::{Duration, SystemTime};
+//!
+//! librpm::config::read_file(None).unwrap();
+//!
+//! let now = SystemTime::now();
+//!
+//! for transaction in history::between(now - Duration::from_secs(86400), now) {
+//!     for package in transaction.packages() {
+//!         println!("{}", package);
+//!     }
+//! }
+//! ```
+
+use crate::internal::{
+    iterator::{IndexKeys, MatchIterator},
+    tag::DBIndexTag,
+};
+use crate::package::Package;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::convert::TryInto;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use streaming_iterator::StreamingIterator;
+
+/// Packages installed by one transaction
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Transaction {
+    id: u32,
+    packages: Vec<Package>,
+}
+
+impl Transaction {
+    /// ID of the transaction (`INSTALLTID`), which is the time it began in
+    /// seconds since the epoch
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Time the transaction began
+    pub fn time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.id.into())
+    }
+
+    /// Packages installed by the transaction which are still installed,
+    /// sorted by install time and then by NEVRA
+    pub fn packages(&self) -> &[Package] {
+        &self.packages
+    }
+}
+
+/// All transactions in the history, oldest first
+pub fn transactions() -> Vec<Transaction> {
+    transaction_ids().into_iter().map(transaction).collect()
+}
+
+/// Transactions which began from `start` (inclusive) until `end`
+/// (exclusive), oldest first.
+///
+/// Only the matching transactions are read, using the `Installtid` index.
+pub fn between(start: SystemTime, end: SystemTime) -> Vec<Transaction> {
+    let (start, end) = (seconds(start), seconds(end));
+
+    if start >= end {
+        return vec![];
+    }
+
+    transaction_ids()
+        .into_iter()
+        .filter(|&id| (start..end).contains(&u64::from(id)))
+        .map(transaction)
+        .collect()
+}
+
+/// IDs of the transactions in the `Installtid` index
+fn transaction_ids() -> BTreeSet<u32> {
+    IndexKeys::new(DBIndexTag::INSTALLTID)
+        .filter_map(|key| key.as_slice().try_into().ok().map(u32::from_ne_bytes))
+        .collect()
+}
+
+/// Read the packages of the transaction with the given ID
+fn transaction(id: u32) -> Transaction {
+    // Integer keys are looked up in host byte order
+    let mut iter = MatchIterator::with_key(DBIndexTag::INSTALLTID, &id.to_ne_bytes());
+    let mut packages = vec![];
+
+    while let Some(header) = iter.next() {
+        if let Ok(mut package) = header.to_package() {
+            package.record = iter.offset();
+            packages.push(package);
+        }
+    }
+
+    packages.sort_by_key(|package| (package.installtime(), package.nevra()));
+    Transaction { id, packages }
+}
+
+/// Whole seconds since the epoch, rounding up so that a transaction ID is
+/// in a range exactly if the time it began is
+fn seconds(time: SystemTime) -> u64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() + u64::from(duration.subsec_nanos() > 0),
+        Err(_) => 0,
+    }
+}
@@ -19,9 +19,13 @@
 //!
 //! We hide the guts of how we interact with librpm until we're sure it's safe to expose
 
+pub(crate) mod ds;
+pub(crate) mod fd;
 pub(crate) mod global_state;
 pub(crate) mod header;
 pub(crate) mod iterator;
+pub(crate) mod payload;
+pub(crate) mod signature;
 pub(crate) mod tag;
 pub(crate) mod td;
 pub(crate) mod ts;
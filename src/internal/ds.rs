@@ -0,0 +1,129 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Dependency sets: librpm's representation of dependencies (`rpmds`)
+
+use super::{header::Header, tag::Tag};
+use crate::error::{Error, ErrorKind};
+use crate::package::{Dependency, DependencyFlags};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+/// Set of dependencies, positioned on one of them (`rpmds`)
+pub(crate) struct DependencySet(librpm_sys::rpmds);
+
+impl DependencySet {
+    /// Create a set holding a single dependency of the kind given by `tag`
+    /// (e.g. `Tag::REQUIRENAME`).
+    ///
+    /// Fails if the dependency contains null bytes.
+    pub(crate) fn single(tag: Tag, dependency: &Dependency) -> Result<Self, Error> {
+        let c_string = |s: &str| {
+            CString::new(s).map_err(|e| {
+                format_err!(
+                    ErrorKind::InvalidInput,
+                    "invalid dependency: {} ({})",
+                    dependency,
+                    e
+                )
+            })
+        };
+
+        let name = c_string(dependency.name())?;
+        let version = dependency.version().map(c_string).transpose()?;
+
+        let ds = unsafe {
+            librpm_sys::rpmdsSingle(
+                tag as librpm_sys::rpmTagVal,
+                name.as_ptr(),
+                version.as_ref().map(|v| v.as_ptr()).unwrap_or(ptr::null()),
+                dependency.flags().bits(),
+            )
+        };
+
+        // librpm positions single dependency sets on their only entry
+        Ok(DependencySet(ds))
+    }
+
+    /// Load the dependencies of the kind given by `tag` from a header
+    pub(crate) fn from_header(header: &Header, tag: Tag) -> Self {
+        let ds = unsafe { librpm_sys::rpmdsNew(header.as_ptr(), tag as librpm_sys::rpmTagVal, 0) };
+        DependencySet(ds)
+    }
+
+    /// Move to the next dependency in the set, returning false at the end
+    pub(crate) fn advance(&mut self) -> bool {
+        unsafe { librpm_sys::rpmdsNext(self.0) >= 0 }
+    }
+
+    /// The current dependency
+    pub(crate) fn dependency(&self) -> Option<Dependency> {
+        let name = c_str(unsafe { librpm_sys::rpmdsN(self.0) })?;
+        let version = c_str(unsafe { librpm_sys::rpmdsEVR(self.0) });
+        let flags = DependencyFlags::from_bits(unsafe { librpm_sys::rpmdsFlags(self.0) });
+
+        // Strings from librpm can't contain null bytes
+        Dependency::new(name, flags, version).ok()
+    }
+
+    /// Does the name, epoch, version and release of the package with the
+    /// given header satisfy the current dependency? (`rpmdsNVRMatchesDep`)
+    pub(crate) fn matches_nevr(&self, header: &Header) -> bool {
+        // Epochs are never promoted (librpm ignores this since 4.16)
+        let nopromote = 1;
+        unsafe { librpm_sys::rpmdsNVRMatchesDep(header.as_ptr(), self.0, nopromote) != 0 }
+    }
+
+    /// Do the current dependencies of both sets overlap, i.e. have the same
+    /// name and intersecting version ranges? (`rpmdsCompare`)
+    pub(crate) fn overlaps(&self, other: &DependencySet) -> bool {
+        unsafe { librpm_sys::rpmdsCompare(self.0, other.0) != 0 }
+    }
+
+    /// Does any dependency in this set overlap the current one of `other`?
+    pub(crate) fn any_overlaps(&mut self, other: &DependencySet) -> bool {
+        while self.advance() {
+            if self.overlaps(other) {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl Drop for DependencySet {
+    fn drop(&mut self) {
+        unsafe {
+            librpm_sys::rpmdsFree(self.0);
+        }
+    }
+}
+
+/// Copy a string owned by librpm, if any
+fn c_str(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    Some(
+        unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
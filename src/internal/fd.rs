@@ -0,0 +1,148 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! File descriptors: librpm's I/O API (`FD_t` from librpmio)
+
+use super::GlobalState;
+use crate::error::{Error, ErrorKind};
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::RawFd;
+use std::path::Path;
+
+/// librpmio file descriptor (`FD_t`), closed when dropped
+///
+/// librpmio stacks I/O layers (e.g. decompressors) on top of a descriptor,
+/// which is how package payloads are read. Like the rest of librpm, it's
+/// only called holding the global lock.
+pub(crate) struct FileDescriptor(librpm_sys::FD_t);
+
+impl FileDescriptor {
+    /// Open the file at the given path. `mode` is an `fopen(3)` style mode
+    /// optionally followed by an I/O type, e.g. `r.ufdio`.
+    pub(crate) fn open(path: &Path, mode: &str) -> Result<Self, Error> {
+        let path_cstr = CString::new(path.as_os_str().as_bytes()).map_err(|e| {
+            format_err!(
+                ErrorKind::InvalidInput,
+                "invalid path: {} ({})",
+                path.display(),
+                e
+            )
+        })?;
+
+        let mode_cstr = CString::new(mode).unwrap();
+
+        let _global_state = GlobalState::lock();
+        let ptr = unsafe { librpm_sys::Fopen(path_cstr.as_ptr(), mode_cstr.as_ptr()) };
+
+        if ptr.is_null() {
+            fail!(ErrorKind::Io, "couldn't open {}", path.display());
+        }
+
+        let fd = FileDescriptor(ptr);
+
+        if let Some(msg) = fd.error() {
+            fail!(ErrorKind::Io, "couldn't open {}: {}", path.display(), msg);
+        }
+
+        Ok(fd)
+    }
+
+    /// Wrap a duplicate of an open file descriptor (`fdDup`), e.g. to have
+    /// librpm write to a `File`. The duplicate is closed when dropped.
+    pub(crate) fn dup(fd: RawFd) -> Result<Self, Error> {
+        let _global_state = GlobalState::lock();
+        let ptr = unsafe { librpm_sys::fdDup(fd) };
+
+        if ptr.is_null() {
+            let err = io::Error::last_os_error();
+            return Err(
+                format_err!(ErrorKind::Io, "couldn't duplicate file descriptor: {}", err)
+                    .with_source(err),
+            );
+        }
+
+        Ok(FileDescriptor(ptr))
+    }
+
+    /// Push an I/O layer onto this descriptor (`Fdopen`), e.g. `r.xz` to
+    /// decompress everything read from it from now on.
+    pub(crate) fn push_io(&mut self, mode: &str) -> Result<(), Error> {
+        let mode_cstr = CString::new(mode).map_err(|e| {
+            format_err!(
+                ErrorKind::InvalidInput,
+                "invalid I/O mode: {} ({})",
+                mode,
+                e
+            )
+        })?;
+
+        let _global_state = GlobalState::lock();
+
+        // `Fdopen` returns the same descriptor it was given on success
+        let ptr = unsafe { librpm_sys::Fdopen(self.0, mode_cstr.as_ptr()) };
+
+        if ptr.is_null() {
+            fail!(ErrorKind::InvalidInput, "unsupported I/O mode: {}", mode);
+        }
+
+        self.0 = ptr;
+
+        if let Some(msg) = self.error() {
+            fail!(ErrorKind::Io, "couldn't open {} stream: {}", mode, msg);
+        }
+
+        Ok(())
+    }
+
+    /// Description of the error state of this descriptor, if any
+    pub(crate) fn error(&self) -> Option<String> {
+        let _global_state = GlobalState::lock();
+
+        if unsafe { librpm_sys::Ferror(self.0) } == 0 {
+            return None;
+        }
+
+        let msg = unsafe { librpm_sys::Fstrerror(self.0) };
+
+        if msg.is_null() {
+            Some("unknown error".to_owned())
+        } else {
+            Some(
+                unsafe { CStr::from_ptr(msg) }
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+
+    /// Obtain the internal pointer to the file descriptor
+    pub(crate) fn as_ptr(&self) -> librpm_sys::FD_t {
+        self.0
+    }
+}
+
+impl Drop for FileDescriptor {
+    fn drop(&mut self) {
+        let _global_state = GlobalState::lock();
+
+        unsafe {
+            librpm_sys::Fclose(self.0);
+        }
+    }
+}
@@ -22,37 +22,117 @@
 //!
 //! This struct tracks changes to librpm's global state based on functions we
 //! have (or have not) invoked.
+//!
+//! librpm isn't thread-safe, so all calls into it are made holding the lock
+//! on the global state. The lock is reentrant: the thread holding it may take
+//! it again, which allows e.g. database queries nested in an iteration, as
+//! iterators hold the lock for as long as they are alive.
 
 use super::ts::TransactionSet;
 use once_cell::sync::Lazy;
-use std::sync::{Mutex, MutexGuard};
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::{Condvar, Mutex};
+use std::thread::{self, ThreadId};
 
-static RPM_GLOBAL_STATE: Lazy<Mutex<GlobalState>> =
-    Lazy::new(|| Mutex::new(GlobalState::default()));
+static RPM_GLOBAL_STATE: Lazy<GlobalLock> = Lazy::new(|| GlobalLock {
+    owner: Mutex::new(Owner::default()),
+    released: Condvar::new(),
+    state: GlobalState::default(),
+});
 
 /// Tracking struct for mutable global state in RPM
 pub(crate) struct GlobalState {
     /// Have any configuration functions been called? (Specifically any ones
     /// which invoke `rpmInitCrypto`, which it seems should only be called once)
-    pub configured: bool,
+    pub configured: Cell<bool>,
 
     /// Global shared transaction set created the first time librpm's global
     /// state is accessed.
     pub ts: TransactionSet,
+
+    /// Number of `GlobalTS` holding the global transaction set, which may be
+    /// nested on the thread holding the lock
+    pub ts_users: Cell<usize>,
 }
 
 impl Default for GlobalState {
     fn default() -> GlobalState {
         GlobalState {
-            configured: false,
+            configured: Cell::new(false),
             ts: TransactionSet::create(),
+            ts_users: Cell::new(0),
         }
     }
 }
 
 impl GlobalState {
-    /// Obtain an exclusive lock to the global state
-    pub fn lock() -> MutexGuard<'static, Self> {
-        RPM_GLOBAL_STATE.lock().unwrap()
+    /// Obtain an exclusive lock to the global state, waiting for any other
+    /// thread holding it. The current thread may already hold it.
+    pub fn lock() -> GlobalStateGuard {
+        let lock = &*RPM_GLOBAL_STATE;
+        let current = thread::current().id();
+        let mut owner = lock.owner.lock().unwrap();
+
+        while matches!(owner.thread, Some(thread) if thread != current) {
+            owner = lock.released.wait(owner).unwrap();
+        }
+
+        owner.thread = Some(current);
+        owner.count += 1;
+
+        GlobalStateGuard(PhantomData)
+    }
+}
+
+/// Lock on the global state, which the thread holding it can take again
+struct GlobalLock {
+    /// Thread holding the lock, if any
+    owner: Mutex<Owner>,
+
+    /// Signalled when the lock is released
+    released: Condvar,
+
+    /// State guarded by the lock
+    state: GlobalState,
+}
+
+/// The state is only accessed through guards, which only exist on the thread
+/// holding the lock (they aren't `Send`)
+unsafe impl Sync for GlobalLock {}
+
+/// Holder of the global lock
+#[derive(Default)]
+struct Owner {
+    /// Thread holding the lock
+    thread: Option<ThreadId>,
+
+    /// Number of guards held by that thread
+    count: usize,
+}
+
+/// Guard giving access to the global state, releasing the lock once the
+/// last guard on the thread holding it is dropped
+pub(crate) struct GlobalStateGuard(PhantomData<*const ()>);
+
+impl Deref for GlobalStateGuard {
+    type Target = GlobalState;
+
+    fn deref(&self) -> &GlobalState {
+        &RPM_GLOBAL_STATE.state
+    }
+}
+
+impl Drop for GlobalStateGuard {
+    fn drop(&mut self) {
+        let lock = &*RPM_GLOBAL_STATE;
+        let mut owner = lock.owner.lock().unwrap();
+        owner.count -= 1;
+
+        if owner.count == 0 {
+            owner.thread = None;
+            lock.released.notify_one();
+        }
     }
 }
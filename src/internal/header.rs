@@ -17,14 +17,57 @@
 
 //! RPM package headers
 
-use super::{tag::Tag, td::TagData};
-use crate::Package;
-use std::mem;
+#[cfg(feature = "serde")]
+use super::tag;
+use super::{
+    fd::FileDescriptor,
+    signature::to_hex,
+    tag::{Tag, TagType},
+    td::TagData,
+    ts::GlobalTS,
+};
+use crate::error::{Error, ErrorKind};
+use crate::logging;
+use crate::package::Package;
+#[cfg(feature = "serde")]
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_uint, c_void};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::{mem, ptr, slice};
 
-/// RPM package header
-pub(crate) struct Header(*mut librpm_sys::headerToken_s);
+/// RPM package header: the tags and data describing a package
+///
+/// Clones of a `Header` refer to the same underlying data, so changes made
+/// through one are visible through all of them. Use `Header::copy` to obtain
+/// an independent header before modifying it.
+pub struct Header(*mut librpm_sys::headerToken_s);
+
+/// Magic number at the start of headers in package files
+pub(crate) const HEADER_MAGIC: [u8; 8] = [0x8e, 0xad, 0xe8, 0x01, 0x00, 0x00, 0x00, 0x00];
+
+/// How tag data is written into a header
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum PutMode {
+    /// Add a tag which isn't present yet
+    Add,
+
+    /// Append to the values of an array tag, adding it if needed
+    Append,
+
+    /// Replace the data of a tag which is already present
+    Modify,
+}
 
 impl Header {
+    /// Create a new, empty header (`headerNew`)
+    pub fn new() -> Self {
+        let ffi_header = unsafe { librpm_sys::headerNew() };
+        assert!(!ffi_header.is_null(), "headerNew returned NULL");
+        Header(ffi_header)
+    }
+
     pub(crate) unsafe fn from_ptr(ffi_header: librpm_sys::Header) -> Self {
         assert!(!ffi_header.is_null());
         // Increment librpm's internal reference count for this header
@@ -32,8 +75,80 @@ impl Header {
         Header(ffi_header)
     }
 
+    /// Read the header of the package file open as `fd`, which is left
+    /// positioned at the start of the payload.
+    pub(crate) fn read_package(fd: &FileDescriptor, path: &Path) -> Result<Self, Error> {
+        let path_cstr = CString::new(path.as_os_str().as_bytes()).map_err(|e| {
+            format_err!(
+                ErrorKind::InvalidInput,
+                "invalid path: {} ({})",
+                path.display(),
+                e
+            )
+        })?;
+
+        let mut txn = GlobalTS::create();
+        let mut ffi_header: librpm_sys::Header = ptr::null_mut();
+
+        let (rc, log) = logging::observe(|| unsafe {
+            librpm_sys::rpmReadPackageFile(
+                txn.as_mut_ptr(),
+                fd.as_ptr(),
+                path_cstr.as_ptr(),
+                &mut ffi_header,
+            )
+        });
+
+        // Packages signed with keys which aren't in the keyring are still
+        // perfectly readable
+        match rc {
+            librpm_sys::rpmRC_e_RPMRC_OK
+            | librpm_sys::rpmRC_e_RPMRC_NOKEY
+            | librpm_sys::rpmRC_e_RPMRC_NOTTRUSTED => (),
+            librpm_sys::rpmRC_e_RPMRC_NOTFOUND => {
+                return Err(format_err!(
+                    ErrorKind::Header,
+                    "not an RPM package: {}",
+                    path.display()
+                )
+                .with_log(log));
+            }
+            // Other failures are digest or signature mismatches, which the
+            // log explains
+            _ => {
+                let kind =
+                    ErrorKind::from_rc(rc, ErrorKind::BadSignature).unwrap_or(ErrorKind::Header);
+
+                return Err(format_err!(
+                    kind,
+                    "error reading package header from: {}",
+                    path.display()
+                )
+                .with_log(log));
+            }
+        }
+
+        if ffi_header.is_null() {
+            fail!(ErrorKind::Header, "no header in: {}", path.display());
+        }
+
+        // `rpmReadPackageFile` hands us a reference we now own
+        Ok(Header(ffi_header))
+    }
+
+    /// Obtain the internal pointer to the header
+    pub(crate) fn as_ptr(&self) -> librpm_sys::Header {
+        self.0
+    }
+
     /// Get the data that corresponds to the given header tag.
-    pub(crate) fn get(&self, tag: Tag) -> Option<TagData<'_>> {
+    pub fn get(&self, tag: Tag) -> Option<TagData> {
+        self.get_by_id(tag as librpm_sys::rpmTagVal)
+    }
+
+    /// Get the data that corresponds to the given raw tag number, which
+    /// need not be known to this crate.
+    pub(crate) fn get_by_id(&self, tag: librpm_sys::rpmTagVal) -> Option<TagData> {
         // Create a zeroed `rpmtd_s` and then immediately initialize it
         let mut td: librpm_sys::rpmtd_s = unsafe { mem::zeroed() };
         unsafe {
@@ -43,7 +158,7 @@ impl Header {
         let rc = unsafe {
             librpm_sys::headerGet(
                 self.0,
-                tag as i32,
+                tag,
                 &mut td,
                 librpm_sys::headerGetFlags_e_HEADERGET_MINMEM,
             )
@@ -53,12 +168,31 @@ impl Header {
             return None;
         }
 
+        // Tags librpm doesn't know have no declared return type
+        let is_array = match unsafe { librpm_sys::rpmTagGetReturnType(tag) } {
+            librpm_sys::rpmTagReturnType_e_RPM_ARRAY_RETURN_TYPE => true,
+            librpm_sys::rpmTagReturnType_e_RPM_SCALAR_RETURN_TYPE => false,
+            _ => td.count > 1,
+        };
+
         let data = match td.type_ {
             librpm_sys::rpmTagType_e_RPM_NULL_TYPE => TagData::Null,
             librpm_sys::rpmTagType_e_RPM_CHAR_TYPE => unsafe { TagData::char(&td) },
+            librpm_sys::rpmTagType_e_RPM_INT8_TYPE if is_array => unsafe {
+                TagData::int8_array(&td)
+            },
             librpm_sys::rpmTagType_e_RPM_INT8_TYPE => unsafe { TagData::int8(&td) },
+            librpm_sys::rpmTagType_e_RPM_INT16_TYPE if is_array => unsafe {
+                TagData::int16_array(&td)
+            },
             librpm_sys::rpmTagType_e_RPM_INT16_TYPE => unsafe { TagData::int16(&td) },
+            librpm_sys::rpmTagType_e_RPM_INT32_TYPE if is_array => unsafe {
+                TagData::int32_array(&td)
+            },
             librpm_sys::rpmTagType_e_RPM_INT32_TYPE => unsafe { TagData::int32(&td) },
+            librpm_sys::rpmTagType_e_RPM_INT64_TYPE if is_array => unsafe {
+                TagData::int64_array(&td)
+            },
             librpm_sys::rpmTagType_e_RPM_INT64_TYPE => unsafe { TagData::int64(&td) },
             librpm_sys::rpmTagType_e_RPM_STRING_TYPE => unsafe { TagData::string(&td) },
             librpm_sys::rpmTagType_e_RPM_STRING_ARRAY_TYPE => unsafe { TagData::string_array(&td) },
@@ -67,24 +201,396 @@ impl Header {
             other => panic!("unsupported rpmtd tag type: {}", other),
         };
 
+        // The data has been copied out, so release anything librpm allocated
+        // for it (e.g. string arrays and extension tags)
+        unsafe {
+            librpm_sys::rpmtdFreeData(&mut td);
+        }
+
         Some(data)
     }
 
-    /// Convert this `Header` into a `Package`
-    pub(crate) fn to_package(&self) -> Package {
-        Package {
-            name: self.get(Tag::NAME).unwrap().as_str().unwrap().to_owned(),
-            epoch: self
-                .get(Tag::EPOCH)
-                .map(|d| d.to_int32().unwrap().to_owned()),
-            version: self.get(Tag::VERSION).unwrap().as_str().unwrap().to_owned(),
-            release: self.get(Tag::RELEASE).unwrap().as_str().unwrap().to_owned(),
-            arch: self.get(Tag::ARCH).map(|d| d.as_str().unwrap().to_owned()),
-            license: self.get(Tag::LICENSE).unwrap().as_str().unwrap().to_owned(),
-            summary: self.get(Tag::SUMMARY).unwrap().as_str().unwrap().into(),
-            description: self.get(Tag::DESCRIPTION).unwrap().as_str().unwrap().into(),
-            buildtime: self.get(Tag::BUILDTIME).unwrap().to_int32().unwrap(),
+    /// Is the given tag present in this header?
+    pub fn contains(&self, tag: Tag) -> bool {
+        unsafe { librpm_sys::headerIsEntry(self.0, tag as librpm_sys::rpmTagVal) != 0 }
+    }
+
+    /// Create an independent copy of this header (`headerCopy`)
+    pub fn copy(&self) -> Header {
+        let ffi_header = unsafe { librpm_sys::headerCopy(self.0) };
+        assert!(!ffi_header.is_null(), "headerCopy returned NULL");
+        Header(ffi_header)
+    }
+
+    /// Serialize this header into its on-disk representation
+    /// (`headerExport`), as stored in the RPM database.
+    pub fn export(&self) -> Vec<u8> {
+        let mut size: c_uint = 0;
+        let blob = unsafe { librpm_sys::headerExport(self.0, &mut size) };
+        assert!(!blob.is_null(), "headerExport returned NULL");
+
+        let bytes = unsafe { slice::from_raw_parts(blob as *const u8, size as usize) }.to_vec();
+
+        unsafe {
+            librpm_sys::rfree(blob);
+        }
+
+        bytes
+    }
+
+    /// Parse a header from its on-disk representation (`headerImport`).
+    ///
+    /// The blob may start with the header magic, as it does in package files
+    /// and the header ranges of repository metadata. Blobs with a damaged
+    /// immutable region, or whose header digests don't match, are rejected.
+    pub fn import(blob: &[u8]) -> Result<Self, Error> {
+        let blob = blob.strip_prefix(&HEADER_MAGIC[..]).unwrap_or(blob);
+        let mut txn = GlobalTS::create();
+        let mut msg: *mut c_char = ptr::null_mut();
+
+        let rc = unsafe {
+            librpm_sys::headerCheck(
+                txn.as_mut_ptr(),
+                blob.as_ptr() as *const c_void,
+                blob.len(),
+                &mut msg,
+            )
+        };
+
+        let msg = if msg.is_null() {
+            None
+        } else {
+            let s = unsafe { CStr::from_ptr(msg) }
+                .to_string_lossy()
+                .into_owned();
+
+            unsafe {
+                librpm_sys::rfree(msg as *mut c_void);
+            }
+
+            Some(s)
+        };
+
+        if rc == librpm_sys::rpmRC_e_RPMRC_FAIL {
+            fail!(
+                ErrorKind::Header,
+                "invalid header: {}",
+                msg.unwrap_or_else(|| "verification failed".to_owned())
+            );
+        }
+
+        Self::import_unchecked(blob)
+    }
+
+    /// Parse a header from its on-disk representation (without magic),
+    /// checking only its structure.
+    pub(crate) fn import_unchecked(blob: &[u8]) -> Result<Self, Error> {
+        let size = match c_uint::try_from(blob.len()) {
+            Ok(size) => size,
+            Err(_) => fail!(
+                ErrorKind::Header,
+                "header blob too large: {} bytes",
+                blob.len()
+            ),
+        };
+
+        // `HEADERIMPORT_COPY` makes librpm copy the blob instead of taking
+        // ownership of it
+        let ffi_header = unsafe {
+            librpm_sys::headerImport(
+                blob.as_ptr() as *mut c_void,
+                size,
+                librpm_sys::headerImportFlags_e_HEADERIMPORT_COPY,
+            )
+        };
+
+        if ffi_header.is_null() {
+            fail!(ErrorKind::Header, "couldn't import header blob");
+        }
+
+        Ok(Header(ffi_header))
+    }
+
+    /// Convert this header to its on-disk representation and back
+    /// (`headerReload`), placing its data in an immutable region marked with
+    /// the given tag (normally `Tag::HEADERIMMUTABLE`).
+    ///
+    /// Headers built with `Header::new` have no immutable region until they
+    /// are reloaded.
+    pub fn reload(self, region: Tag) -> Result<Self, Error> {
+        let ffi_header = self.0;
+
+        // `headerReload` releases the reference it is given, even on failure
+        mem::forget(self);
+
+        let reloaded =
+            unsafe { librpm_sys::headerReload(ffi_header, region as librpm_sys::rpmTagVal) };
+
+        if reloaded.is_null() {
+            fail!(ErrorKind::Header, "couldn't reload header");
+        }
+
+        Ok(Header(reloaded))
+    }
+
+    /// Add a tag which isn't present in this header yet.
+    ///
+    /// The data must match the type librpm declares for the tag, e.g.
+    /// `TagData::Str` for `Tag::NAME` or `TagData::Int32Array` for
+    /// `Tag::FILESIZES`. Single values are accepted for array tags.
+    pub fn put<D: Into<TagData>>(&mut self, tag: Tag, data: D) -> Result<(), Error> {
+        if self.contains(tag) {
+            fail!(
+                ErrorKind::InvalidInput,
+                "tag already present: {}",
+                tag.name()
+            );
+        }
+
+        self.put_data(tag, &data.into(), PutMode::Add)
+    }
+
+    /// Append values to an array tag, adding the tag if it isn't present.
+    pub fn append<D: Into<TagData>>(&mut self, tag: Tag, data: D) -> Result<(), Error> {
+        if !tag.is_array() && tag.tag_type() != Some(TagType::STRING_ARRAY) {
+            fail!(
+                ErrorKind::InvalidInput,
+                "can't append to non-array tag: {}",
+                tag.name()
+            );
         }
+
+        self.put_data(tag, &data.into(), PutMode::Append)
+    }
+
+    /// Replace the data of a tag which is already present in this header.
+    pub fn modify<D: Into<TagData>>(&mut self, tag: Tag, data: D) -> Result<(), Error> {
+        if !self.contains(tag) {
+            fail!(ErrorKind::NotFound, "tag not present: {}", tag.name());
+        }
+
+        self.put_data(tag, &data.into(), PutMode::Modify)
+    }
+
+    /// Remove a tag from this header, returning whether it was present
+    pub fn remove(&mut self, tag: Tag) -> bool {
+        unsafe { librpm_sys::headerDel(self.0, tag as librpm_sys::rpmTagVal) == 0 }
+    }
+
+    /// Check `data` against the declared type of `tag` and write it into
+    /// this header.
+    fn put_data(&mut self, tag: Tag, data: &TagData, mode: PutMode) -> Result<(), Error> {
+        let tag_type = match tag.tag_type() {
+            Some(tag_type) => tag_type,
+            None => fail!(ErrorKind::Header, "unknown type for tag: {}", tag.name()),
+        };
+
+        let compatible = match (tag_type, data) {
+            (TagType::I18NSTRING, TagData::Str(_)) | (TagType::STRING_ARRAY, TagData::Str(_)) => {
+                true
+            }
+            (_, data) if data.is_int_array() => tag.is_array() && data.tag_type() == tag_type,
+            (_, data) => data.tag_type() == tag_type,
+        };
+
+        if !compatible || tag_type == TagType::NULL {
+            fail!(
+                ErrorKind::InvalidInput,
+                "type mismatch for tag {}: expected {:?}, got {:?}",
+                tag.name(),
+                tag_type,
+                data.tag_type()
+            );
+        }
+
+        // Backing storage for the pointers passed to librpm, which copies
+        // the data into the header
+        let byte = match *data {
+            TagData::Char(c) => u8::try_from(u32::from(c)).map_err(|_| {
+                format_err!(ErrorKind::InvalidInput, "not a single-byte char: {:?}", c)
+            })?,
+            _ => 0,
+        };
+        let mut strings: Vec<CString> = vec![];
+        let mut string_ptrs: Vec<*const c_char> = vec![];
+
+        let (count, ptr): (usize, *const c_void) = match *data {
+            TagData::Null => unreachable!(),
+            TagData::Char(_) => (1, void_ptr(&byte)),
+            TagData::Int8(ref n) => (1, void_ptr(n)),
+            TagData::Int16(ref n) => (1, void_ptr(n)),
+            TagData::Int32(ref n) => (1, void_ptr(n)),
+            TagData::Int64(ref n) => (1, void_ptr(n)),
+            TagData::Int8Array(ref v) => (v.len(), v.as_ptr() as *const c_void),
+            TagData::Int16Array(ref v) => (v.len(), v.as_ptr() as *const c_void),
+            TagData::Int32Array(ref v) => (v.len(), v.as_ptr() as *const c_void),
+            TagData::Int64Array(ref v) => (v.len(), v.as_ptr() as *const c_void),
+            TagData::Bin(ref v) => (v.len(), v.as_ptr() as *const c_void),
+            TagData::Str(ref s) | TagData::I18NStr(ref s) => {
+                strings.push(c_string(tag, s)?);
+
+                if tag_type == TagType::STRING {
+                    (1, strings[0].as_ptr() as *const c_void)
+                } else {
+                    // I18N strings are passed as arrays (one per locale), and
+                    // a single string for a string array tag as one of one
+                    string_ptrs.push(strings[0].as_ptr());
+                    (1, string_ptrs.as_ptr() as *const c_void)
+                }
+            }
+            TagData::StrArray(ref v) => {
+                for s in v {
+                    strings.push(c_string(tag, s)?);
+                }
+
+                string_ptrs.extend(strings.iter().map(|s| s.as_ptr()));
+                (string_ptrs.len(), string_ptrs.as_ptr() as *const c_void)
+            }
+        };
+
+        if count == 0 {
+            fail!(ErrorKind::InvalidInput, "no data for tag: {}", tag.name());
+        }
+
+        let mut td: librpm_sys::rpmtd_s = unsafe { mem::zeroed() };
+        unsafe {
+            librpm_sys::rpmtdReset(&mut td);
+        }
+
+        td.tag = tag as librpm_sys::rpmTagVal;
+        td.type_ = tag_type as librpm_sys::rpm_tagtype_t;
+        td.count = count as librpm_sys::rpm_count_t;
+        td.data = ptr as librpm_sys::rpm_data_t;
+
+        let rc = unsafe {
+            match mode {
+                PutMode::Add => librpm_sys::headerPut(
+                    self.0,
+                    &mut td,
+                    librpm_sys::headerPutFlags_e_HEADERPUT_DEFAULT,
+                ),
+                PutMode::Append => librpm_sys::headerPut(
+                    self.0,
+                    &mut td,
+                    librpm_sys::headerPutFlags_e_HEADERPUT_APPEND,
+                ),
+                PutMode::Modify => librpm_sys::headerMod(self.0, &mut td),
+            }
+        };
+
+        if rc != 1 {
+            fail!(ErrorKind::Header, "couldn't write tag: {}", tag.name());
+        }
+
+        Ok(())
+    }
+
+    /// Raw numbers of all tags present in this header, in header order
+    #[cfg(feature = "serde")]
+    pub(crate) fn tag_ids(&self) -> Vec<librpm_sys::rpmTagVal> {
+        let mut tags = vec![];
+
+        unsafe {
+            let iter = librpm_sys::headerInitIterator(self.0);
+
+            loop {
+                let tag = librpm_sys::headerNextTag(iter);
+
+                if tag == Tag::NOT_FOUND as librpm_sys::rpmTagVal {
+                    break;
+                }
+
+                tags.push(tag);
+            }
+
+            librpm_sys::headerFreeIterator(iter);
+        }
+
+        tags
+    }
+
+    /// Get the value of a string tag, if present
+    fn get_string(&self, tag: Tag) -> Option<String> {
+        self.get(tag).and_then(|d| d.as_str().map(str::to_owned))
+    }
+
+    /// Get the value of a string tag every package has
+    fn required_string(&self, tag: Tag) -> Result<String, Error> {
+        self.get_string(tag)
+            .ok_or_else(|| format_err!(ErrorKind::Header, "missing {} tag", tag.name()))
+    }
+
+    /// Convert this `Header` into a `Package`.
+    ///
+    /// Fails if the header lacks any of the tags every package has: `NAME`,
+    /// `VERSION`, `RELEASE`, `LICENSE`, `SUMMARY`, `DESCRIPTION` and
+    /// `BUILDTIME`.
+    pub fn to_package(&self) -> Result<Package, Error> {
+        Ok(Package {
+            name: self.required_string(Tag::NAME)?,
+            epoch: self.get(Tag::EPOCH).and_then(|d| d.to_int32()),
+            version: self.required_string(Tag::VERSION)?,
+            release: self.required_string(Tag::RELEASE)?,
+            arch: self.get_string(Tag::ARCH),
+            license: self.required_string(Tag::LICENSE)?,
+            summary: self.required_string(Tag::SUMMARY)?,
+            description: self.required_string(Tag::DESCRIPTION)?,
+            buildtime: self
+                .get(Tag::BUILDTIME)
+                .and_then(|d| d.to_int32())
+                .ok_or_else(|| {
+                    format_err!(ErrorKind::Header, "missing {} tag", Tag::BUILDTIME.name())
+                })?,
+            url: self.get_string(Tag::URL),
+            vendor: self.get_string(Tag::VENDOR),
+            packager: self.get_string(Tag::PACKAGER),
+            buildhost: self.get_string(Tag::BUILDHOST),
+            group: self.get_string(Tag::GROUP),
+            size: self
+                .get(Tag::LONGSIZE)
+                .and_then(|d| d.to_int64())
+                .or_else(|| {
+                    self.get(Tag::SIZE)
+                        .and_then(|d| d.to_int32())
+                        .map(i64::from)
+                })
+                .and_then(|size| u64::try_from(size).ok()),
+            installtime: self
+                .get(Tag::INSTALLTIME)
+                .and_then(|d| d.to_int32())
+                .map(|time| time as u32),
+            sourcerpm: self.get_string(Tag::SOURCERPM),
+            distribution: self.get_string(Tag::DISTRIBUTION),
+            disttag: self.get_string(Tag::DISTTAG),
+            os: self.get_string(Tag::OS),
+            platform: self.get_string(Tag::PLATFORM),
+            rpmversion: self.get_string(Tag::RPMVERSION),
+            cookie: self.get_string(Tag::COOKIE),
+            payload_format: self.get_string(Tag::PAYLOADFORMAT),
+            payload_compressor: self.get_string(Tag::PAYLOADCOMPRESSOR),
+            optflags: self.get_string(Tag::OPTFLAGS),
+            record: None,
+            sha1header: self.get_string(Tag::SHA1HEADER),
+            sha256header: self.get_string(Tag::SHA256HEADER),
+            pkgid: self.get(Tag::SIGMD5).and_then(|d| d.as_bytes().map(to_hex)),
+            installtid: self
+                .get(Tag::INSTALLTID)
+                .and_then(|d| d.to_int32())
+                .map(|tid| tid as u32),
+            path: None,
+        })
+    }
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Header::new()
+    }
+}
+
+impl Clone for Header {
+    fn clone(&self) -> Self {
+        unsafe { Header::from_ptr(self.0) }
     }
 }
 
@@ -96,3 +602,49 @@ impl Drop for Header {
         }
     }
 }
+
+/// Serialize the whole header as a map of tag names to their data.
+///
+/// Tags librpm has no name for are keyed by their number.
+#[cfg(feature = "serde")]
+impl Serialize for Header {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries: Vec<(String, TagData)> = self
+            .tag_ids()
+            .into_iter()
+            .filter_map(|id| {
+                let name = tag::tag_name(id)
+                    .map(ToOwned::to_owned)
+                    .unwrap_or_else(|| id.to_string());
+
+                self.get_by_id(id).map(|data| (name, data))
+            })
+            .collect();
+
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+
+        for (name, data) in &entries {
+            map.serialize_entry(name, data)?;
+        }
+
+        map.end()
+    }
+}
+
+/// Convert a string for storage in a header
+fn c_string(tag: Tag, s: &str) -> Result<CString, Error> {
+    CString::new(s).map_err(|e| {
+        format_err!(
+            ErrorKind::InvalidInput,
+            "invalid string for tag {}: {}",
+            tag.name(),
+            e
+        )
+    })
+}
+
+/// Obtain an untyped pointer to a value
+fn void_ptr<T>(value: &T) -> *const c_void {
+    let ptr: *const T = value;
+    ptr.cast()
+}
@@ -17,10 +17,16 @@
 
 //! Iterators for matches in the RPM database
 
-use super::{header::Header, tag::DBIndexTag, ts::GlobalTS};
+use super::{
+    global_state::GlobalStateGuard,
+    header::Header,
+    tag::{DBIndexTag, Tag},
+    ts::TransactionSet,
+    GlobalState,
+};
 #[cfg(feature = "regex")]
 use regex::Regex;
-use std::{os::raw::c_void, ptr};
+use std::{ffi::CString, os::raw::c_void, ptr, slice};
 use streaming_iterator::StreamingIterator;
 
 /// Iterator over the matches from a database query
@@ -28,60 +34,112 @@ pub(crate) struct MatchIterator {
     /// Pointer to librpm's match iterator.
     ptr: *mut librpm_sys::rpmdbMatchIterator_s,
 
-    /// Hold the lock on the global transaction set while reading data.
-    /// This ensures nothing else can make calls to librpm while we are iterating over its data
+    /// Transaction set (and database handle) private to this iterator, so
+    /// other queries can run while it is alive, including nested ones.
+    /// Freed after the iterator (see `Drop`).
     #[allow(dead_code)]
-    txn: GlobalTS,
+    txn: TransactionSet,
 
     /// Next item in the iterator
     next_item: Option<Header>,
 
     /// Have we already finished iterating?
     finished: bool,
+
+    /// Hold the global lock for as long as the iterator is alive, as librpm
+    /// isn't thread-safe. The lock is reentrant, so queries nested in the
+    /// iteration still work. Released last, after the transaction set.
+    _global_state: GlobalStateGuard,
 }
 
 impl MatchIterator {
     /// Create a new `MatchIterator` for the current RPM database, searching
     /// by the (optionally) given search key.
     pub(crate) fn new(tag: DBIndexTag, key_opt: Option<&str>) -> Self {
-        let mut txn = GlobalTS::create();
-        let next_item = None;
-        let finished = false;
-
-        if let Some(key) = key_opt {
-            if !key.is_empty() {
-                let ptr = unsafe {
-                    librpm_sys::rpmtsInitIterator(
-                        txn.as_mut_ptr(),
-                        tag as librpm_sys::rpm_tag_t,
-                        key.as_ptr() as *const c_void,
-                        key.len(),
-                    )
-                };
-
-                return Self {
-                    ptr,
-                    txn,
-                    next_item,
-                    finished,
-                };
-            }
+        match key_opt {
+            Some(key) if !key.is_empty() => Self::init(tag, Some(key.as_bytes())),
+            _ => Self::init(tag, None),
         }
+    }
+
+    /// Create a `MatchIterator` for the package stored under the given
+    /// database record number.
+    pub(crate) fn record(record: u32) -> Self {
+        // Record numbers are looked up in host byte order
+        Self::with_key(DBIndexTag::PACKAGES, &record.to_ne_bytes())
+    }
+
+    /// Create a `MatchIterator` searching by a raw key, e.g. an integer in
+    /// host byte order.
+    pub(crate) fn with_key(tag: DBIndexTag, key: &[u8]) -> Self {
+        Self::init(tag, Some(key))
+    }
+
+    /// Create a `MatchIterator` searching by the given raw key, if any
+    fn init(tag: DBIndexTag, key_opt: Option<&[u8]>) -> Self {
+        let global_state = GlobalState::lock();
+        let mut txn = TransactionSet::create();
+
+        let (key_ptr, key_len) = match key_opt {
+            Some(key) => (key.as_ptr() as *const c_void, key.len()),
+            None => (ptr::null(), 0),
+        };
 
         let ptr = unsafe {
             librpm_sys::rpmtsInitIterator(
-                txn.as_mut_ptr(),
+                *txn.as_mut_ptr(),
                 tag as librpm_sys::rpm_tag_t,
-                ptr::null(),
-                0,
+                key_ptr,
+                key_len,
             )
         };
 
         Self {
             ptr,
             txn,
-            next_item,
-            finished,
+            next_item: None,
+            finished: false,
+            _global_state: global_state,
+        }
+    }
+
+    /// Only return headers where the given tag is exactly `value`
+    /// (`rpmdbSetIteratorRE` with `RPMMIRE_STRCMP`).
+    ///
+    /// A missing epoch matches `0`, and values containing null bytes match
+    /// nothing.
+    pub(crate) fn filter_tag(&mut self, tag: Tag, value: &str) {
+        // An iterator without matches has nothing to filter
+        if self.ptr.is_null() {
+            return;
+        }
+
+        // No header value contains a null byte
+        let pattern = match CString::new(value) {
+            Ok(pattern) => pattern,
+            Err(_) => {
+                self.finished = true;
+                return;
+            }
+        };
+
+        unsafe {
+            librpm_sys::rpmdbSetIteratorRE(
+                self.ptr,
+                tag as librpm_sys::rpmTagVal,
+                librpm_sys::rpmMireMode_e_RPMMIRE_STRCMP,
+                pattern.as_ptr(),
+            );
+        }
+    }
+
+    /// Database record number of the current header (`rpmdbGetIteratorOffset`)
+    pub(crate) fn offset(&self) -> Option<u32> {
+        self.next_item.as_ref()?;
+
+        match unsafe { librpm_sys::rpmdbGetIteratorOffset(self.ptr) } {
+            0 => None,
+            offset => Some(offset),
         }
     }
 }
@@ -118,3 +176,77 @@ impl Drop for MatchIterator {
         }
     }
 }
+
+/// Iterator over the keys in a database index (`rpmdbIndexIterator`)
+pub(crate) struct IndexKeys {
+    /// Pointer to librpm's index iterator, null if the database couldn't be
+    /// opened
+    ptr: librpm_sys::rpmdbIndexIterator,
+
+    /// Transaction set holding the database open, freed after the iterator
+    #[allow(dead_code)]
+    txn: TransactionSet,
+
+    /// Hold the global lock for as long as the iterator is alive (see
+    /// `MatchIterator`)
+    _global_state: GlobalStateGuard,
+}
+
+impl IndexKeys {
+    /// Iterate over the keys of the given index in the current RPM database
+    pub(crate) fn new(tag: DBIndexTag) -> Self {
+        let global_state = GlobalState::lock();
+        let mut txn = TransactionSet::create();
+        let ts = *txn.as_mut_ptr();
+
+        // Unlike match iterators, index iterators need an open database
+        let ptr = unsafe {
+            if librpm_sys::rpmtsOpenDB(ts, libc::O_RDONLY) == 0 {
+                librpm_sys::rpmdbIndexIteratorInit(
+                    librpm_sys::rpmtsGetRdb(ts),
+                    tag as librpm_sys::rpmDbiTag,
+                )
+            } else {
+                ptr::null_mut()
+            }
+        };
+
+        Self {
+            ptr,
+            txn,
+            _global_state: global_state,
+        }
+    }
+}
+
+impl Iterator for IndexKeys {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if self.ptr.is_null() {
+            return None;
+        }
+
+        let mut key: *const c_void = ptr::null();
+        let mut key_len = 0;
+
+        if unsafe { librpm_sys::rpmdbIndexIteratorNext(self.ptr, &mut key, &mut key_len) } != 0 {
+            return None;
+        }
+
+        if key.is_null() {
+            return Some(vec![]);
+        }
+
+        // The key is only valid until the next call
+        Some(unsafe { slice::from_raw_parts(key as *const u8, key_len) }.to_vec())
+    }
+}
+
+impl Drop for IndexKeys {
+    fn drop(&mut self) {
+        unsafe {
+            librpm_sys::rpmdbIndexIteratorFree(self.ptr);
+        }
+    }
+}
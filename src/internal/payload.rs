@@ -0,0 +1,266 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Package payloads: the (compressed) archive of files in a `.rpm`
+
+use super::{fd::FileDescriptor, header::Header, tag::Tag, GlobalState};
+use crate::error::{Error, ErrorKind};
+use crate::package::FileMetadata;
+use std::ffi::CStr;
+use std::fs::File;
+use std::io;
+use std::os::raw::{c_char, c_int, c_void};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr;
+
+/// Compressor librpm assumes when a package doesn't declare one
+const DEFAULT_COMPRESSOR: &str = "gzip";
+
+/// Payload compressors librpmio can decompress, by the names packages
+/// declare them with (its other I/O types don't decompress anything)
+const COMPRESSORS: [&str; 5] = ["gzip", "bzip2", "xz", "lzma", "zstd"];
+
+/// Reader for the archive entries in a package payload
+///
+/// After `next_entry` returns an entry, its contents can be read through the
+/// `io::Read` impl until it returns 0, or written out with `read_to_file`.
+///
+/// Every call into librpm takes the global lock, but unlike iterators the
+/// reader doesn't hold it between calls: its file info and archive iterator
+/// are private to it, so other threads may use librpm meanwhile.
+pub(crate) struct PayloadReader {
+    /// librpm's archive iterator
+    fi: librpm_sys::rpmfi,
+
+    /// File info from the package header, which the iterator refers to
+    files: librpm_sys::rpmfiles,
+
+    /// Decompressed payload stream (dropped after the iterator)
+    #[allow(dead_code)]
+    fd: FileDescriptor,
+
+    /// Name of the payload compressor, e.g. `xz`
+    compressor: String,
+
+    /// Have we already reached the end of the archive?
+    finished: bool,
+}
+
+impl PayloadReader {
+    /// Open the package file at the given path and start reading its payload
+    pub(crate) fn open(path: &Path) -> Result<Self, Error> {
+        let mut fd = FileDescriptor::open(path, "r.ufdio")?;
+        let header = Header::read_package(&fd, path)?;
+
+        let compressor = header
+            .get(Tag::PAYLOADCOMPRESSOR)
+            .and_then(|d| d.as_str().map(ToOwned::to_owned))
+            .unwrap_or_else(|| DEFAULT_COMPRESSOR.to_owned());
+
+        if !COMPRESSORS.contains(&compressor.as_str()) {
+            fail!(
+                ErrorKind::Header,
+                "unsupported payload compressor in {}: {}",
+                path.display(),
+                compressor
+            );
+        }
+
+        // This is how rpm2cpio selects the decompressor. librpmio may still
+        // have been built without support for it.
+        fd.push_io(&format!("r.{}", compressor))?;
+
+        let _global_state = GlobalState::lock();
+
+        let files = unsafe {
+            librpm_sys::rpmfilesNew(
+                ptr::null_mut(),
+                header.as_ptr(),
+                Tag::BASENAMES as librpm_sys::rpmTagVal,
+                librpm_sys::rpmfiFlags_e_RPMFI_KEEPHEADER,
+            )
+        };
+
+        if files.is_null() {
+            fail!(ErrorKind::Header, "no file info in: {}", path.display());
+        }
+
+        let fi = unsafe {
+            librpm_sys::rpmfiNewArchiveReader(
+                fd.as_ptr(),
+                files,
+                librpm_sys::rpmFileIter_e_RPMFI_ITER_READ_ARCHIVE as c_int,
+            )
+        };
+
+        if fi.is_null() {
+            unsafe {
+                librpm_sys::rpmfilesFree(files);
+            }
+
+            fail!(
+                ErrorKind::Io,
+                "couldn't read payload of: {}",
+                path.display()
+            );
+        }
+
+        Ok(PayloadReader {
+            fi,
+            files,
+            fd,
+            compressor,
+            finished: false,
+        })
+    }
+
+    /// Name of the compressor the payload was decompressed with
+    pub(crate) fn compressor(&self) -> &str {
+        &self.compressor
+    }
+
+    /// Advance to the next entry in the archive, returning `None` at its end
+    pub(crate) fn next_entry(&mut self) -> Result<Option<FileMetadata>, Error> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let _global_state = GlobalState::lock();
+        let rc = unsafe { librpm_sys::rpmfiNext(self.fi) };
+
+        if rc >= 0 {
+            return Ok(Some(unsafe { file_metadata(self.fi) }));
+        }
+
+        self.finished = true;
+
+        if rc == librpm_sys::rpmfilesErrorCodes_RPMERR_ITER_END {
+            Ok(None)
+        } else {
+            Err(archive_error(rc))
+        }
+    }
+
+    /// Write the contents of the current entry to `file`
+    /// (`rpmfiArchiveReadToFile`), checking them against the file digest in
+    /// the header.
+    pub(crate) fn read_to_file(&mut self, file: &File) -> Result<(), Error> {
+        if self.finished {
+            fail!(ErrorKind::Io, "no current entry in package payload");
+        }
+
+        let _global_state = GlobalState::lock();
+        let fd = FileDescriptor::dup(file.as_raw_fd())?;
+
+        let nodigest = 0;
+        let rc = unsafe { librpm_sys::rpmfiArchiveReadToFile(self.fi, fd.as_ptr(), nodigest) };
+
+        if rc != 0 {
+            return Err(archive_error(rc));
+        }
+
+        Ok(())
+    }
+}
+
+impl io::Read for PayloadReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.finished {
+            return Ok(0);
+        }
+
+        let _global_state = GlobalState::lock();
+        let n = unsafe {
+            librpm_sys::rpmfiArchiveRead(self.fi, buf.as_mut_ptr() as *mut c_void, buf.len())
+        };
+
+        // Errors are reported as `(size_t)-1`
+        if n > buf.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "error reading package payload",
+            ));
+        }
+
+        Ok(n)
+    }
+}
+
+impl Drop for PayloadReader {
+    fn drop(&mut self) {
+        let _global_state = GlobalState::lock();
+
+        unsafe {
+            librpm_sys::rpmfiArchiveClose(self.fi);
+            librpm_sys::rpmfiFree(self.fi);
+            librpm_sys::rpmfilesFree(self.files);
+        }
+    }
+}
+
+/// Read the metadata of the current entry of an archive iterator
+unsafe fn file_metadata(fi: librpm_sys::rpmfi) -> FileMetadata {
+    let link_target = c_string(librpm_sys::rpmfiFLink(fi)).filter(|l| !l.is_empty());
+
+    FileMetadata {
+        path: c_string(librpm_sys::rpmfiFN(fi)).unwrap_or_default(),
+        mode: u32::from(librpm_sys::rpmfiFMode(fi)),
+        size: librpm_sys::rpmfiFSize(fi),
+        link_target,
+        user: c_string(librpm_sys::rpmfiFUser(fi)).unwrap_or_default(),
+        group: c_string(librpm_sys::rpmfiFGroup(fi)).unwrap_or_default(),
+        mtime: librpm_sys::rpmfiFMtime(fi),
+        nlink: librpm_sys::rpmfiFNlink(fi),
+        inode: librpm_sys::rpmfiFInode(fi),
+        has_content: librpm_sys::rpmfiArchiveHasContent(fi) != 0,
+    }
+}
+
+/// Copy a string owned by librpm, if the pointer is non-NULL
+unsafe fn c_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+    }
+}
+
+/// Convert an archive error code into an `Error`
+fn archive_error(rc: c_int) -> Error {
+    let kind = if rc == librpm_sys::rpmfilesErrorCodes_RPMERR_DIGEST_MISMATCH {
+        ErrorKind::BadSignature
+    } else {
+        ErrorKind::Io
+    };
+
+    let msg = unsafe { librpm_sys::rpmfileStrerror(rc) };
+
+    if msg.is_null() {
+        return format_err!(kind, "error reading package payload ({})", rc);
+    }
+
+    let err = format_err!(kind, "error reading package payload: {}", unsafe {
+        CStr::from_ptr(msg).to_string_lossy()
+    });
+
+    unsafe {
+        librpm_sys::rfree(msg as *mut c_void);
+    }
+
+    err
+}
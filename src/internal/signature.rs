@@ -0,0 +1,316 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Signature headers of package files
+
+use super::{
+    header::{Header, HEADER_MAGIC},
+    tag::SignatureTag,
+    td::TagData,
+    GlobalState,
+};
+use crate::error::{Error, ErrorKind};
+use std::ffi::CStr;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::os::raw::c_uint;
+use std::path::Path;
+use std::ptr;
+
+/// Size of the lead which starts every package file
+const LEAD_SIZE: usize = 96;
+
+/// Magic number at the start of the lead
+const LEAD_MAGIC: [u8; 4] = [0xed, 0xab, 0xee, 0xdb];
+
+/// Maximum number of index entries librpm accepts in a header
+const MAX_INDEX_ENTRIES: u32 = 0xffff;
+
+/// Maximum size of the data section librpm accepts in a header
+const MAX_DATA_SIZE: u32 = 0x0fff_ffff;
+
+/// Tags of the signature header which hold OpenPGP signatures
+const SIGNATURE_TAGS: [SignatureTag; 4] = [
+    SignatureTag::RSA,
+    SignatureTag::DSA,
+    SignatureTag::PGP,
+    SignatureTag::GPG,
+];
+
+/// Signature header of a package file: sizes, digests and signatures of the
+/// main header and payload
+pub struct SignatureHeader(Header);
+
+impl SignatureHeader {
+    /// Read the signature header of the package file at the given path
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .map_err(|e| format_err!(ErrorKind::Io, "{}: {}", path.display(), e).with_source(e))?;
+
+        Self::read(BufReader::new(file))
+    }
+
+    /// Read the signature header from a package file, positioned at its
+    /// start (i.e. at the lead)
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, Error> {
+        let mut lead = [0u8; LEAD_SIZE];
+        reader.read_exact(&mut lead)?;
+
+        if lead[..4] != LEAD_MAGIC {
+            fail!(ErrorKind::Header, "not an RPM package (bad lead magic)");
+        }
+
+        let mut intro = [0u8; 16];
+        reader.read_exact(&mut intro)?;
+
+        if intro[..8] != HEADER_MAGIC {
+            fail!(ErrorKind::Header, "bad signature header magic");
+        }
+
+        let index_entries = u32::from_be_bytes([intro[8], intro[9], intro[10], intro[11]]);
+        let data_size = u32::from_be_bytes([intro[12], intro[13], intro[14], intro[15]]);
+
+        if index_entries > MAX_INDEX_ENTRIES || data_size > MAX_DATA_SIZE {
+            fail!(
+                ErrorKind::Header,
+                "signature header too large ({} tags, {} bytes)",
+                index_entries,
+                data_size
+            );
+        }
+
+        // librpm expects the blob without the magic, but with the counts
+        let mut blob = intro[8..].to_vec();
+        let body_size = index_entries as usize * 16 + data_size as usize;
+        blob.resize(8 + body_size, 0);
+        reader.read_exact(&mut blob[8..])?;
+
+        Header::import_unchecked(&blob).map(SignatureHeader)
+    }
+
+    /// Get the data that corresponds to the given signature tag
+    pub fn get(&self, tag: SignatureTag) -> Option<TagData> {
+        self.0.get_by_id(tag as librpm_sys::rpmTagVal)
+    }
+
+    /// Size of the main header and (compressed) payload in bytes
+    pub fn size(&self) -> Option<u64> {
+        self.integer(SignatureTag::LONGSIZE)
+            .or_else(|| self.integer(SignatureTag::SIZE))
+    }
+
+    /// Size of the uncompressed payload in bytes
+    pub fn payload_size(&self) -> Option<u64> {
+        self.integer(SignatureTag::LONGARCHIVESIZE)
+            .or_else(|| self.integer(SignatureTag::PAYLOADSIZE))
+    }
+
+    /// MD5 digest of the main header and payload, as a hex string
+    pub fn md5(&self) -> Option<String> {
+        self.get(SignatureTag::MD5)
+            .and_then(|data| data.as_bytes().map(to_hex))
+    }
+
+    /// SHA1 digest of the main header, as a hex string
+    pub fn sha1(&self) -> Option<String> {
+        self.get(SignatureTag::SHA1)
+            .and_then(|data| data.as_str().map(ToOwned::to_owned))
+    }
+
+    /// SHA256 digest of the main header, as a hex string
+    pub fn sha256(&self) -> Option<String> {
+        self.get(SignatureTag::SHA256)
+            .and_then(|data| data.as_str().map(ToOwned::to_owned))
+    }
+
+    /// OpenPGP signatures in this header
+    pub fn signatures(&self) -> Vec<Signature> {
+        SIGNATURE_TAGS
+            .iter()
+            .filter_map(|&tag| {
+                let data = self.get(tag)?;
+                let packet = data.as_bytes()?;
+                Some(Signature::new(tag, packet.to_vec()))
+            })
+            .collect()
+    }
+
+    /// The signature header as a plain `Header`, e.g. to list its tags
+    pub fn as_header(&self) -> &Header {
+        &self.0
+    }
+
+    /// Obtain an integer tag, whatever its width
+    fn integer(&self, tag: SignatureTag) -> Option<u64> {
+        match self.get(tag)? {
+            TagData::Int32(n) => Some(u64::from(n as u32)),
+            TagData::Int64(n) => Some(n as u64),
+            _ => None,
+        }
+    }
+}
+
+/// OpenPGP signature from a signature header
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Signature {
+    /// Tag the signature was stored under
+    tag: SignatureTag,
+
+    /// Raw OpenPGP signature packet
+    packet: Vec<u8>,
+
+    /// Public key algorithm ID (RFC 4880 section 9.1)
+    pubkey_algorithm: Option<u8>,
+
+    /// Hash algorithm ID (RFC 4880 section 9.4)
+    hash_algorithm: Option<u8>,
+
+    /// ID of the signing key
+    key_id: Option<[u8; 8]>,
+}
+
+impl Signature {
+    /// Parse the algorithms and signing key ID out of a signature packet
+    /// (`pgpPrtParams`)
+    fn new(tag: SignatureTag, packet: Vec<u8>) -> Self {
+        let _global_state = GlobalState::lock();
+        let mut params: librpm_sys::pgpDigParams = ptr::null_mut();
+
+        let rc = unsafe {
+            librpm_sys::pgpPrtParams(
+                packet.as_ptr(),
+                packet.len(),
+                librpm_sys::pgpTag_e_PGPTAG_SIGNATURE as c_uint,
+                &mut params,
+            )
+        };
+
+        if rc != 0 || params.is_null() {
+            return Signature {
+                tag,
+                packet,
+                pubkey_algorithm: None,
+                hash_algorithm: None,
+                key_id: None,
+            };
+        }
+
+        let (pubkey_algorithm, hash_algorithm, key_id) = unsafe {
+            (
+                librpm_sys::pgpDigParamsAlgo(
+                    params,
+                    librpm_sys::pgpValType_e_PGPVAL_PUBKEYALGO as c_uint,
+                ),
+                librpm_sys::pgpDigParamsAlgo(
+                    params,
+                    librpm_sys::pgpValType_e_PGPVAL_HASHALGO as c_uint,
+                ),
+                sign_id(params),
+            )
+        };
+
+        unsafe {
+            librpm_sys::pgpDigParamsFree(params);
+        }
+
+        Signature {
+            tag,
+            packet,
+            pubkey_algorithm: u8::try_from(pubkey_algorithm).ok(),
+            hash_algorithm: u8::try_from(hash_algorithm).ok(),
+            key_id,
+        }
+    }
+
+    /// Tag the signature was stored under. `RSA` and `DSA` signatures cover
+    /// the main header only, `PGP` and `GPG` ones the header and payload.
+    pub fn tag(&self) -> SignatureTag {
+        self.tag
+    }
+
+    /// Raw OpenPGP signature packet
+    pub fn packet(&self) -> &[u8] {
+        &self.packet
+    }
+
+    /// Name of the public key algorithm, e.g. `RSA`
+    pub fn pubkey_algorithm(&self) -> Option<&'static str> {
+        self.pubkey_algorithm
+            .and_then(|id| val_string(librpm_sys::pgpValType_e_PGPVAL_PUBKEYALGO, id))
+    }
+
+    /// Name of the hash algorithm, e.g. `SHA256`
+    pub fn hash_algorithm(&self) -> Option<&'static str> {
+        self.hash_algorithm
+            .and_then(|id| val_string(librpm_sys::pgpValType_e_PGPVAL_HASHALGO, id))
+    }
+
+    /// ID of the signing key, as a hex string (as shown by `rpm -K`).
+    ///
+    /// This is the key ID librpm found in the signature
+    /// (`pgpDigParamsSignID`). Whether it's also derived from an issuer
+    /// fingerprint, when the signature has no issuer key ID, depends on
+    /// librpm's OpenPGP implementation.
+    pub fn key_id(&self) -> Option<String> {
+        self.key_id.map(|id| to_hex(&id))
+    }
+}
+
+/// Copy the signing key ID out of parsed signature parameters. librpm
+/// leaves it zeroed when the signature doesn't name its key.
+unsafe fn sign_id(params: librpm_sys::pgpDigParams) -> Option<[u8; 8]> {
+    let ptr = librpm_sys::pgpDigParamsSignID(params);
+
+    if ptr.is_null() {
+        return None;
+    }
+
+    let mut id = [0u8; 8];
+    id.copy_from_slice(std::slice::from_raw_parts(ptr, 8));
+
+    if id == [0; 8] {
+        None
+    } else {
+        Some(id)
+    }
+}
+
+/// Obtain librpm's name for an OpenPGP value (`pgpValString`)
+fn val_string(val_type: librpm_sys::pgpValType_e, id: u8) -> Option<&'static str> {
+    let _global_state = GlobalState::lock();
+    let ptr = unsafe { librpm_sys::pgpValString(val_type as librpm_sys::pgpValType, id) };
+
+    if ptr.is_null() {
+        return None;
+    }
+
+    // Names come from static tables inside librpm
+    unsafe { CStr::from_ptr(ptr) }.to_str().ok()
+}
+
+/// Format bytes as a lowercase hex string
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+
+    for byte in bytes {
+        write!(hex, "{:02x}", byte).unwrap();
+    }
+
+    hex
+}
@@ -25,6 +25,7 @@
 )]
 
 use crate::Index;
+use std::ffi::CStr;
 
 /// Identifiers for data in RPM headers (`rpmTag_e` in librpm)
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -55,6 +56,7 @@ pub enum Tag {
     DSAHEADER = librpm_sys::rpmTag_e_RPMTAG_DSAHEADER as isize,
     RSAHEADER = librpm_sys::rpmTag_e_RPMTAG_RSAHEADER as isize,
     SHA1HEADER = librpm_sys::rpmTag_e_RPMTAG_SHA1HEADER as isize,
+    SHA256HEADER = librpm_sys::rpmTag_e_RPMTAG_SHA256HEADER as isize,
     LONGSIGSIZE = librpm_sys::rpmTag_e_RPMTAG_LONGSIGSIZE as isize,
     LONGARCHIVESIZE = librpm_sys::rpmTag_e_RPMTAG_LONGARCHIVESIZE as isize,
     NAME = librpm_sys::rpmTag_e_RPMTAG_NAME as isize,
@@ -273,12 +275,66 @@ pub enum Tag {
     OBSOLETENEVRS = librpm_sys::rpmTag_e_RPMTAG_OBSOLETENEVRS as isize,
     CONFLICTNEVRS = librpm_sys::rpmTag_e_RPMTAG_CONFLICTNEVRS as isize,
     FILENLINKS = librpm_sys::rpmTag_e_RPMTAG_FILENLINKS as isize,
+    FILETRIGGERSCRIPTS = librpm_sys::rpmTag_e_RPMTAG_FILETRIGGERSCRIPTS as isize,
+    FILETRIGGERSCRIPTPROG = librpm_sys::rpmTag_e_RPMTAG_FILETRIGGERSCRIPTPROG as isize,
+    FILETRIGGERSCRIPTFLAGS = librpm_sys::rpmTag_e_RPMTAG_FILETRIGGERSCRIPTFLAGS as isize,
+    FILETRIGGERNAME = librpm_sys::rpmTag_e_RPMTAG_FILETRIGGERNAME as isize,
+    FILETRIGGERINDEX = librpm_sys::rpmTag_e_RPMTAG_FILETRIGGERINDEX as isize,
+    FILETRIGGERVERSION = librpm_sys::rpmTag_e_RPMTAG_FILETRIGGERVERSION as isize,
+    FILETRIGGERFLAGS = librpm_sys::rpmTag_e_RPMTAG_FILETRIGGERFLAGS as isize,
+    TRANSFILETRIGGERSCRIPTS = librpm_sys::rpmTag_e_RPMTAG_TRANSFILETRIGGERSCRIPTS as isize,
+    TRANSFILETRIGGERSCRIPTPROG = librpm_sys::rpmTag_e_RPMTAG_TRANSFILETRIGGERSCRIPTPROG as isize,
+    TRANSFILETRIGGERSCRIPTFLAGS = librpm_sys::rpmTag_e_RPMTAG_TRANSFILETRIGGERSCRIPTFLAGS as isize,
+    TRANSFILETRIGGERNAME = librpm_sys::rpmTag_e_RPMTAG_TRANSFILETRIGGERNAME as isize,
+    TRANSFILETRIGGERINDEX = librpm_sys::rpmTag_e_RPMTAG_TRANSFILETRIGGERINDEX as isize,
+    TRANSFILETRIGGERVERSION = librpm_sys::rpmTag_e_RPMTAG_TRANSFILETRIGGERVERSION as isize,
+    TRANSFILETRIGGERFLAGS = librpm_sys::rpmTag_e_RPMTAG_TRANSFILETRIGGERFLAGS as isize,
+    FILETRIGGERPRIORITIES = librpm_sys::rpmTag_e_RPMTAG_FILETRIGGERPRIORITIES as isize,
+    TRANSFILETRIGGERPRIORITIES = librpm_sys::rpmTag_e_RPMTAG_TRANSFILETRIGGERPRIORITIES as isize,
+}
+
+impl Tag {
+    /// Name librpm uses for this tag (e.g. `Name`, `Version`)
+    pub fn name(self) -> &'static str {
+        tag_name(self as librpm_sys::rpmTagVal).unwrap_or("(unknown)")
+    }
+
+    /// Type of data librpm declares for this tag (`rpmTagGetTagType`)
+    pub fn tag_type(self) -> Option<TagType> {
+        let flags = unsafe { librpm_sys::rpmTagGetTagType(self as librpm_sys::rpmTagVal) };
+        TagType::from_raw(flags & librpm_sys::RPM_MASK_TYPE)
+    }
+
+    /// Does this tag hold an array of values (as opposed to a single one)?
+    pub fn is_array(self) -> bool {
+        let return_type = unsafe { librpm_sys::rpmTagGetReturnType(self as librpm_sys::rpmTagVal) };
+        return_type == librpm_sys::rpmTagReturnType_e_RPM_ARRAY_RETURN_TYPE
+    }
+}
+
+/// Obtain the name of a raw tag number, if librpm knows it
+pub(crate) fn tag_name(tag: librpm_sys::rpmTagVal) -> Option<&'static str> {
+    let ptr = unsafe { librpm_sys::rpmTagGetName(tag) };
+
+    if ptr.is_null() {
+        return None;
+    }
+
+    // Tag names come from a static table inside librpm
+    let name = unsafe { CStr::from_ptr(ptr) }.to_str().ok()?;
+
+    if name == "(unknown)" {
+        None
+    } else {
+        Some(name)
+    }
 }
 
 impl From<Index> for DBIndexTag {
     fn from(i: Index) -> Self {
         match i {
             Index::Name => DBIndexTag::NAME,
+            Index::Triggers => DBIndexTag::TRIGGERNAME,
         }
     }
 }
@@ -350,6 +406,9 @@ pub enum SignatureTag {
 
     /// Uncompressed payload size in bytes
     LONGARCHIVESIZE = librpm_sys::rpmSigTag_e_RPMSIGTAG_LONGARCHIVESIZE as isize,
+
+    /// SHA256 header digest
+    SHA256 = librpm_sys::rpmSigTag_e_RPMSIGTAG_SHA256 as isize,
 }
 
 /// Types of data in tags from headers (`rpmTagType_e` in librpm)
@@ -367,6 +426,27 @@ pub enum TagType {
     I18NSTRING = librpm_sys::rpmTagType_e_RPM_I18NSTRING_TYPE as isize,
 }
 
+impl TagType {
+    /// Convert a raw `rpmTagType` into a `TagType`
+    pub(crate) fn from_raw(raw: u32) -> Option<Self> {
+        let tag_type = match raw {
+            librpm_sys::rpmTagType_e_RPM_NULL_TYPE => TagType::NULL,
+            librpm_sys::rpmTagType_e_RPM_CHAR_TYPE => TagType::CHAR,
+            librpm_sys::rpmTagType_e_RPM_INT8_TYPE => TagType::INT8,
+            librpm_sys::rpmTagType_e_RPM_INT16_TYPE => TagType::INT16,
+            librpm_sys::rpmTagType_e_RPM_INT32_TYPE => TagType::INT32,
+            librpm_sys::rpmTagType_e_RPM_INT64_TYPE => TagType::INT64,
+            librpm_sys::rpmTagType_e_RPM_STRING_TYPE => TagType::STRING,
+            librpm_sys::rpmTagType_e_RPM_BIN_TYPE => TagType::BIN,
+            librpm_sys::rpmTagType_e_RPM_STRING_ARRAY_TYPE => TagType::STRING_ARRAY,
+            librpm_sys::rpmTagType_e_RPM_I18NSTRING_TYPE => TagType::I18NSTRING,
+            _ => return None,
+        };
+
+        Some(tag_type)
+    }
+}
+
 /// Classes of data in tags from headers (`rpmTagClass_e` in librpm)
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum TagClass {
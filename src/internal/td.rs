@@ -21,13 +21,16 @@
 #![allow(dead_code)]
 
 use super::tag::TagType;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::ffi::CStr;
 use std::os::raw::c_char;
 use std::{slice, str};
 
 /// Data found in RPM headers, associated with a particular `Tag` value.
-#[derive(Debug)]
-pub(crate) enum TagData<'hdr> {
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TagData {
     /// No data associated with this tag
     Null,
 
@@ -46,25 +49,37 @@ pub(crate) enum TagData<'hdr> {
     /// 64-bit integer
     Int64(i64),
 
+    /// 8-bit integer array
+    Int8Array(Vec<i8>),
+
+    /// 16-bit integer array
+    Int16Array(Vec<i16>),
+
+    /// 32-bit integer array
+    Int32Array(Vec<i32>),
+
+    /// 64-bit integer array
+    Int64Array(Vec<i64>),
+
     /// String
-    Str(&'hdr str),
+    Str(String),
 
     /// String array
-    StrArray(Vec<&'hdr str>),
+    StrArray(Vec<String>),
 
     /// Internationalized string (UTF-8?)
-    I18NStr(&'hdr str),
+    I18NStr(String),
 
     /// Binary data
-    Bin(&'hdr [u8]),
+    Bin(Vec<u8>),
 }
 
-impl<'hdr> TagData<'hdr> {
+impl TagData {
     /// Convert an `rpmtd_s` into a `TagData::Char`
     pub(crate) unsafe fn char(td: &librpm_sys::rpmtd_s) -> Self {
         assert_eq!(td.type_, TagType::CHAR as u32);
         let ix = if td.ix >= 0 { td.ix as isize } else { 0 };
-        TagData::Char(*(td.data as *const char).offset(ix))
+        TagData::Char(char::from(*(td.data as *const u8).offset(ix)))
     }
 
     /// Convert an `rpmtd_s` into an `TagData::Int8`
@@ -95,36 +110,56 @@ impl<'hdr> TagData<'hdr> {
         TagData::Int64(*(td.data as *const i64).offset(ix))
     }
 
+    /// Convert an `rpmtd_s` into an `Int8Array`
+    pub(crate) unsafe fn int8_array(td: &librpm_sys::rpmtd_s) -> Self {
+        assert_eq!(td.type_, TagType::INT8 as u32);
+        TagData::Int8Array(slice::from_raw_parts(td.data as *const i8, td.count as usize).to_vec())
+    }
+
+    /// Convert an `rpmtd_s` into an `Int16Array`
+    pub(crate) unsafe fn int16_array(td: &librpm_sys::rpmtd_s) -> Self {
+        assert_eq!(td.type_, TagType::INT16 as u32);
+        TagData::Int16Array(
+            slice::from_raw_parts(td.data as *const i16, td.count as usize).to_vec(),
+        )
+    }
+
+    /// Convert an `rpmtd_s` into an `Int32Array`
+    pub(crate) unsafe fn int32_array(td: &librpm_sys::rpmtd_s) -> Self {
+        assert_eq!(td.type_, TagType::INT32 as u32);
+        TagData::Int32Array(
+            slice::from_raw_parts(td.data as *const i32, td.count as usize).to_vec(),
+        )
+    }
+
+    /// Convert an `rpmtd_s` into an `Int64Array`
+    pub(crate) unsafe fn int64_array(td: &librpm_sys::rpmtd_s) -> Self {
+        assert_eq!(td.type_, TagType::INT64 as u32);
+        TagData::Int64Array(
+            slice::from_raw_parts(td.data as *const i64, td.count as usize).to_vec(),
+        )
+    }
+
     /// Convert an `rpmtd_s` into a `Str`
     pub(crate) unsafe fn string(td: &librpm_sys::rpmtd_s) -> Self {
         assert_eq!(td.type_, TagType::STRING as u32);
-        let cstr = CStr::from_ptr(td.data as *const c_char);
 
         // RPM_STRING_TYPE is ASCII-only. We presently treat it as UTF-8.
-        TagData::Str(str::from_utf8(cstr.to_bytes()).unwrap_or_else(|e| {
-            panic!(
-                "failed to decode RPM_STRING_TYPE as UTF-8 (tag: {}): {}",
-                td.tag, e
-            );
-        }))
+        TagData::Str(decode_str(td.data as *const c_char))
     }
 
     /// Convert an `rpmtd_s` into a `StrArray`
-    pub(crate) unsafe fn string_array(_td: &librpm_sys::rpmtd_s) -> Self {
-        panic!("RPM_STRING_ARRAY_TYPE unsupported!");
+    pub(crate) unsafe fn string_array(td: &librpm_sys::rpmtd_s) -> Self {
+        assert_eq!(td.type_, TagType::STRING_ARRAY as u32);
+        let ptrs = slice::from_raw_parts(td.data as *const *const c_char, td.count as usize);
+
+        TagData::StrArray(ptrs.iter().map(|&ptr| decode_str(ptr)).collect())
     }
 
     /// Convert an `rpmtd_s` into an `I18NStr`
     pub(crate) unsafe fn i18n_string(td: &librpm_sys::rpmtd_s) -> Self {
         assert_eq!(td.type_, TagType::I18NSTRING as u32);
-        let cstr = CStr::from_ptr(td.data as *const c_char);
-
-        TagData::I18NStr(str::from_utf8(cstr.to_bytes()).unwrap_or_else(|e| {
-            panic!(
-                "failed to decode RPM_I18NSTRING_TYPE as UTF-8 (tag: {}): {}",
-                td.tag, e
-            );
-        }))
+        TagData::I18NStr(decode_str(td.data as *const c_char))
     }
 
     /// Convert an `rpmtd_s` into a `Bin`
@@ -144,10 +179,34 @@ impl<'hdr> TagData<'hdr> {
             td.tag
         );
 
-        TagData::Bin(slice::from_raw_parts(
-            td.data as *const u8,
-            td.count as usize,
-        ))
+        TagData::Bin(slice::from_raw_parts(td.data as *const u8, td.count as usize).to_vec())
+    }
+
+    /// Type of this data, as stored in a header
+    pub fn tag_type(&self) -> TagType {
+        match *self {
+            TagData::Null => TagType::NULL,
+            TagData::Char(_) => TagType::CHAR,
+            TagData::Int8(_) | TagData::Int8Array(_) => TagType::INT8,
+            TagData::Int16(_) | TagData::Int16Array(_) => TagType::INT16,
+            TagData::Int32(_) | TagData::Int32Array(_) => TagType::INT32,
+            TagData::Int64(_) | TagData::Int64Array(_) => TagType::INT64,
+            TagData::Str(_) => TagType::STRING,
+            TagData::StrArray(_) => TagType::STRING_ARRAY,
+            TagData::I18NStr(_) => TagType::I18NSTRING,
+            TagData::Bin(_) => TagType::BIN,
+        }
+    }
+
+    /// Is this an array of integers?
+    pub fn is_int_array(&self) -> bool {
+        matches!(
+            *self,
+            TagData::Int8Array(_)
+                | TagData::Int16Array(_)
+                | TagData::Int32Array(_)
+                | TagData::Int64Array(_)
+        )
     }
 
     /// Is this tag data NULL?
@@ -220,12 +279,44 @@ impl<'hdr> TagData<'hdr> {
         self.to_int64().is_some()
     }
 
+    /// Obtain a slice of int8 values, if this is an int8 array
+    pub fn as_int8_array(&self) -> Option<&[i8]> {
+        match *self {
+            TagData::Int8Array(ref a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Obtain a slice of int16 values, if this is an int16 array
+    pub fn as_int16_array(&self) -> Option<&[i16]> {
+        match *self {
+            TagData::Int16Array(ref a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Obtain a slice of int32 values, if this is an int32 array
+    pub fn as_int32_array(&self) -> Option<&[i32]> {
+        match *self {
+            TagData::Int32Array(ref a) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Obtain a slice of int64 values, if this is an int64 array
+    pub fn as_int64_array(&self) -> Option<&[i64]> {
+        match *self {
+            TagData::Int64Array(ref a) => Some(a),
+            _ => None,
+        }
+    }
+
     /// Obtain a string reference, so long as this value is a string type
-    pub fn as_str(&self) -> Option<&'hdr str> {
+    pub fn as_str(&self) -> Option<&str> {
         // We presently treat `STRING` and `I18NSTRING` equivalently
         match *self {
-            TagData::Str(s) => Some(s),
-            TagData::I18NStr(s) => Some(s),
+            TagData::Str(ref s) => Some(s),
+            TagData::I18NStr(ref s) => Some(s),
             _ => None,
         }
     }
@@ -235,8 +326,8 @@ impl<'hdr> TagData<'hdr> {
         self.as_str().is_some()
     }
 
-    /// Obtain a slice of string references, if this value is a string array
-    pub fn as_str_array(&self) -> Option<&[&'hdr str]> {
+    /// Obtain a slice of strings, if this value is a string array
+    pub fn as_str_array(&self) -> Option<&[String]> {
         match *self {
             TagData::StrArray(ref sa) => Some(&sa[..]),
             _ => None,
@@ -251,7 +342,7 @@ impl<'hdr> TagData<'hdr> {
     /// Obtain a byte slice, if this value contains binary data
     pub fn as_bytes(&self) -> Option<&[u8]> {
         match *self {
-            TagData::Bin(b) => Some(b),
+            TagData::Bin(ref b) => Some(b),
             _ => None,
         }
     }
@@ -261,3 +352,52 @@ impl<'hdr> TagData<'hdr> {
         self.as_bytes().is_some()
     }
 }
+
+/// Copy a NUL-terminated string out of an `rpmtd_s`.
+///
+/// Headers aren't guaranteed to be UTF-8 (e.g. old changelogs in Latin-1), so
+/// invalid sequences are replaced with U+FFFD rather than failing.
+unsafe fn decode_str(ptr: *const c_char) -> String {
+    CStr::from_ptr(ptr).to_string_lossy().into_owned()
+}
+
+macro_rules! impl_from {
+    ($type:ty, $variant:ident) => {
+        impl From<$type> for TagData {
+            fn from(value: $type) -> Self {
+                TagData::$variant(value)
+            }
+        }
+    };
+}
+
+impl_from!(char, Char);
+impl_from!(i8, Int8);
+impl_from!(i16, Int16);
+impl_from!(i32, Int32);
+impl_from!(i64, Int64);
+impl_from!(Vec<i8>, Int8Array);
+impl_from!(Vec<i16>, Int16Array);
+impl_from!(Vec<i32>, Int32Array);
+impl_from!(Vec<i64>, Int64Array);
+impl_from!(String, Str);
+impl_from!(Vec<String>, StrArray);
+impl_from!(Vec<u8>, Bin);
+
+impl From<&str> for TagData {
+    fn from(value: &str) -> Self {
+        TagData::Str(value.to_owned())
+    }
+}
+
+impl From<&[&str]> for TagData {
+    fn from(values: &[&str]) -> Self {
+        TagData::StrArray(values.iter().map(|&s| s.to_owned()).collect())
+    }
+}
+
+impl From<&[u8]> for TagData {
+    fn from(value: &[u8]) -> Self {
+        TagData::Bin(value.to_vec())
+    }
+}
@@ -17,25 +17,28 @@
 
 //! Transaction sets: librpm's transaction API
 
-use super::GlobalState;
-use std::sync::atomic::AtomicPtr;
-use std::sync::MutexGuard;
+use super::{global_state::GlobalStateGuard, GlobalState};
+use std::sync::atomic::{AtomicPtr, Ordering};
 
 /// librpm transactions, a.k.a. "transaction sets" (or `rpmts` librpm type)
 ///
 /// Nearly all access to librpm, including actions which don't necessarily
 /// involve operations on the RPM database, require a transaction set.
 ///
-/// This library opens a single global transaction set on command, and all
+/// This library opens a single global transaction set on command, and most
 /// operations which require one acquire it, use it, and then release it.
 /// This allows us to keep them out of the public API.
+///
+/// Database iterators are the exception: they live for as long as the caller
+/// likes, so each one gets a transaction set of its own rather than holding
+/// on to the global one.
 pub(crate) struct TransactionSet(AtomicPtr<librpm_sys::rpmts_s>);
 
 impl TransactionSet {
     /// Create a transaction set (i.e. begin a transaction)
     ///
-    /// This is not intended to be invoked directly, but instead obtained
-    /// from `GlobalState`.
+    /// Outside of iterators, this is not intended to be invoked directly, but
+    /// instead obtained from `GlobalState`.
     pub(crate) fn create() -> Self {
         TransactionSet(AtomicPtr::new(unsafe { librpm_sys::rpmtsCreate() }))
     }
@@ -53,32 +56,49 @@ impl TransactionSet {
     pub(crate) fn as_mut_ptr(&mut self) -> &mut *mut librpm_sys::rpmts_s {
         self.0.get_mut()
     }
+
+    /// Obtain the internal pointer through a shared reference, for the
+    /// global transaction set
+    pub(crate) fn as_ptr(&self) -> *mut librpm_sys::rpmts_s {
+        self.0.load(Ordering::Relaxed)
+    }
 }
 
 /// Crate-public wrapper for acquiring and releasing the global transaction set
 /// which also cleans it prior to unlocking it.
-pub(crate) struct GlobalTS(MutexGuard<'static, GlobalState>);
+pub(crate) struct GlobalTS(GlobalStateGuard);
 
 impl GlobalTS {
-    /// Acquire the global state mutex, giving the current thread exclusive
+    /// Acquire the global state lock, giving the current thread exclusive
     /// access to the global transaction set.
+    ///
+    /// The lock is reentrant, so the current thread may already be using the
+    /// transaction set further up the stack.
     pub fn create() -> Self {
-        GlobalTS(GlobalState::lock())
+        let global_state = GlobalState::lock();
+        global_state.ts_users.set(global_state.ts_users.get() + 1);
+        GlobalTS(global_state)
     }
 
     /// Obtain the internal pointer to the transaction set
     pub(crate) fn as_mut_ptr(&mut self) -> *mut librpm_sys::rpmts_s {
-        // Since we're guaranteed to be holding the GlobalState mutex here,
+        // Since we're guaranteed to be holding the GlobalState lock here,
         // we're free to deref the pointer.
-        *self.0.ts.as_mut_ptr()
+        self.0.ts.as_ptr()
     }
 }
 
-/// Tidy up the shared global transaction set between uses
+/// Tidy up the shared global transaction set between uses, once the
+/// outermost `GlobalTS` on the thread is done with it
 impl Drop for GlobalTS {
     fn drop(&mut self) {
-        unsafe {
-            librpm_sys::rpmtsClean(self.as_mut_ptr());
+        let users = self.0.ts_users.get() - 1;
+        self.0.ts_users.set(users);
+
+        if users == 0 {
+            unsafe {
+                librpm_sys::rpmtsClean(self.as_mut_ptr());
+            }
         }
     }
 }
@@ -35,15 +35,33 @@
 #[macro_use]
 pub mod error;
 
+/// Architecture and OS compatibility (i.e. rpmrc machine tables)
+pub mod arch;
+
 /// RPM configuration (i.e. rpmrc)
 pub mod config;
 
 /// RPM database access
 pub mod db;
 
+/// Differences between sets of packages
+pub mod diff;
+
+/// Dependency graphs of installed packages
+pub mod graph;
+
+/// RPM package headers
+pub mod header;
+
+/// Installation history of the RPM database
+pub mod history;
+
 /// Internal functionality not to be exposed outside of this crate
 mod internal;
 
+/// Logging of librpm's diagnostics (i.e. rpmlog)
+pub mod logging;
+
 /// Macros are RPM's configuration system
 pub mod macro_context;
 
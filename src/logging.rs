@@ -0,0 +1,258 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Routing librpm's diagnostics (rpmlog) through the `log` crate
+//!
+//! By default librpm writes its messages straight to stderr. Calling `init`
+//! forwards them to the `log` crate instead, with the target `librpm`.
+//! Independently of that, `capture` collects the messages emitted during a
+//! single call.
+//!
+//! # Example
+//!
+//! ```
+//! librpm::logging::init();
+//! librpm::logging::set_verbosity(log::LevelFilter::Warn);
+//!
+//! let (result, messages) = librpm::logging::capture(|| librpm::config::read_file(None));
+//! result.unwrap();
+//!
+//! for message in messages {
+//!     println!("{}: {}", message.level(), message.text());
+//! }
+//! ```
+
+use crate::internal::GlobalState;
+use std::cell::RefCell;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Target of the `log` records for librpm's messages
+const LOG_TARGET: &str = "librpm";
+
+/// Has the callback been installed with librpm?
+static CALLBACK_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Are messages forwarded to the `log` crate?
+static FORWARD_TO_LOG: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Messages captured on this thread, one buffer per nested `capture` or
+    /// `observe`, innermost last
+    static CAPTURED: RefCell<Vec<Buffer>> = const { RefCell::new(vec![]) };
+}
+
+/// Messages collected by a `capture` or `observe` call in progress
+struct Buffer {
+    /// Messages emitted so far
+    messages: Vec<Message>,
+
+    /// Are the messages taken away from enclosing captures and stderr (for
+    /// `capture`), or only copied (for `observe`)?
+    take: bool,
+}
+
+/// Message emitted by librpm
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Message {
+    /// Severity of the message
+    level: log::Level,
+
+    /// Text of the message, without trailing newline
+    text: String,
+}
+
+impl Message {
+    /// Severity of the message
+    pub fn level(&self) -> log::Level {
+        self.level
+    }
+
+    /// Text of the message
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/// Forward librpm's messages to the `log` crate instead of stderr.
+///
+/// This is global to the process and can't be undone.
+pub fn init() {
+    FORWARD_TO_LOG.store(true, Ordering::SeqCst);
+    install_callback();
+}
+
+/// Set which messages librpm emits (`rpmSetVerbosity`). librpm's default is
+/// `Info`, and `Trace` is the same as `Debug`.
+///
+/// librpm can't turn off all messages, so `Off` means emergency messages
+/// only. These are still emitted (and forwarded or captured), but librpm
+/// itself doesn't use that priority.
+pub fn set_verbosity(level: log::LevelFilter) {
+    let priority = match level {
+        // A mask of 0 would only query the current one
+        log::LevelFilter::Off => librpm_sys::rpmlogLvl_e_RPMLOG_EMERG,
+        log::LevelFilter::Error => librpm_sys::rpmlogLvl_e_RPMLOG_ERR,
+        log::LevelFilter::Warn => librpm_sys::rpmlogLvl_e_RPMLOG_WARNING,
+        log::LevelFilter::Info => librpm_sys::rpmlogLvl_e_RPMLOG_INFO,
+        log::LevelFilter::Debug | log::LevelFilter::Trace => librpm_sys::rpmlogLvl_e_RPMLOG_DEBUG,
+    };
+
+    // `rpmSetVerbosity` is a macro for this: RPMLOG_UPTO(priority)
+    let mask = (1 << (priority + 1)) - 1;
+
+    let _global_state = GlobalState::lock();
+
+    unsafe {
+        librpm_sys::rpmlogSetMask(mask as c_int);
+    }
+}
+
+/// Run `f`, collecting the messages librpm emits on this thread meanwhile.
+///
+/// Captured messages are still forwarded to the `log` crate if `init` was
+/// called, but aren't written to stderr.
+pub fn capture<T, F: FnOnce() -> T>(f: F) -> (T, Vec<Message>) {
+    install_callback();
+    collect(f, true)
+}
+
+/// Run `f`, copying the messages librpm emits on this thread meanwhile to
+/// explain a failure, while they still go wherever they would otherwise.
+///
+/// The callback isn't installed for this: unless `init` or `capture` did,
+/// only the last warning or error librpm records (if any) is returned.
+pub(crate) fn observe<T, F: FnOnce() -> T>(f: F) -> (T, Vec<Message>) {
+    if CALLBACK_INSTALLED.load(Ordering::SeqCst) {
+        return collect(f, false);
+    }
+
+    let records = unsafe { librpm_sys::rpmlogGetNrecs() };
+    let result = f();
+
+    if unsafe { librpm_sys::rpmlogGetNrecs() } == records {
+        return (result, vec![]);
+    }
+
+    let text_ptr = unsafe { librpm_sys::rpmlogMessage() };
+    let messages = if text_ptr.is_null() {
+        vec![]
+    } else {
+        vec![Message {
+            level: level(unsafe { librpm_sys::rpmlogCode() } as librpm_sys::rpmlogLvl),
+            text: unsafe { message_text(text_ptr) },
+        }]
+    };
+
+    (result, messages)
+}
+
+/// Run `f` with a buffer for the messages emitted meanwhile
+fn collect<T, F: FnOnce() -> T>(f: F, take: bool) -> (T, Vec<Message>) {
+    CAPTURED.with(|captured| {
+        captured.borrow_mut().push(Buffer {
+            messages: vec![],
+            take,
+        })
+    });
+
+    let result = f();
+    let messages = CAPTURED
+        .with(|captured| captured.borrow_mut().pop())
+        .map(|buffer| buffer.messages)
+        .unwrap_or_default();
+
+    (result, messages)
+}
+
+/// Install `log_callback` with librpm, once
+fn install_callback() {
+    let _global_state = GlobalState::lock();
+
+    if !CALLBACK_INSTALLED.swap(true, Ordering::SeqCst) {
+        unsafe {
+            librpm_sys::rpmlogSetCallback(Some(log_callback), ptr::null_mut());
+        }
+    }
+}
+
+/// Callback invoked by librpm for every message it emits
+unsafe extern "C" fn log_callback(rec: librpm_sys::rpmlogRec, _data: *mut c_void) -> c_int {
+    let level = level(librpm_sys::rpmlogRecPriority(rec));
+    let text_ptr = librpm_sys::rpmlogRecMessage(rec);
+
+    let text = if text_ptr.is_null() {
+        String::new()
+    } else {
+        message_text(text_ptr)
+    };
+
+    let forwarded = FORWARD_TO_LOG.load(Ordering::SeqCst);
+
+    if forwarded {
+        log::log!(target: LOG_TARGET, level, "{}", text);
+    }
+
+    // Copy the message into the buffers from the innermost out, up to the
+    // first one which takes it. `try_with` as this might be called during
+    // thread teardown.
+    let captured = CAPTURED
+        .try_with(|captured| {
+            for buffer in captured.borrow_mut().iter_mut().rev() {
+                buffer.messages.push(Message {
+                    level,
+                    text: text.clone(),
+                });
+
+                if buffer.take {
+                    return true;
+                }
+            }
+
+            false
+        })
+        .unwrap_or(false);
+
+    // Let librpm print the message itself if nobody else took it
+    if forwarded || captured {
+        0
+    } else {
+        librpm_sys::RPMLOG_DEFAULT as c_int
+    }
+}
+
+/// Copy the text of a message, without trailing newline
+unsafe fn message_text(ptr: *const c_char) -> String {
+    CStr::from_ptr(ptr).to_string_lossy().trim_end().to_owned()
+}
+
+/// Map an rpmlog priority to a `log::Level`
+fn level(priority: librpm_sys::rpmlogLvl) -> log::Level {
+    match priority {
+        librpm_sys::rpmlogLvl_e_RPMLOG_EMERG
+        | librpm_sys::rpmlogLvl_e_RPMLOG_ALERT
+        | librpm_sys::rpmlogLvl_e_RPMLOG_CRIT
+        | librpm_sys::rpmlogLvl_e_RPMLOG_ERR => log::Level::Error,
+        librpm_sys::rpmlogLvl_e_RPMLOG_WARNING => log::Level::Warn,
+        librpm_sys::rpmlogLvl_e_RPMLOG_NOTICE | librpm_sys::rpmlogLvl_e_RPMLOG_INFO => {
+            log::Level::Info
+        }
+        _ => log::Level::Debug,
+    }
+}
@@ -19,8 +19,11 @@
 //! previous rpmrc system.
 
 use crate::error::{Error, ErrorKind};
+use crate::internal::GlobalState;
 use librpm_sys;
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
 
 /// Scopes in which macros are defined
 pub struct MacroContext(librpm_sys::rpmMacroContext);
@@ -39,6 +42,7 @@ impl MacroContext {
     ///
     /// Level defines the macro recursion level (0 is the entry API)
     pub fn define(&self, macro_string: &str, level: isize) -> Result<(), Error> {
+        let _global_state = GlobalState::lock();
         let cstr =
             CString::new(macro_string).map_err(|e| format_err!(ErrorKind::Config, "{}", e))?;
 
@@ -49,10 +53,39 @@ impl MacroContext {
         Ok(())
     }
 
+    /// Expand all macros in the given string using this context.
+    pub fn expand(&self, macro_string: &str) -> Result<String, Error> {
+        let _global_state = GlobalState::lock();
+        let cstr =
+            CString::new(macro_string).map_err(|e| format_err!(ErrorKind::Config, "{}", e))?;
+
+        let mut obuf: *mut c_char = ptr::null_mut();
+        let rc = unsafe { librpm_sys::rpmExpandMacros(self.0, cstr.as_ptr(), &mut obuf, 0) };
+
+        if obuf.is_null() {
+            fail!(ErrorKind::Macro, "error expanding macro: {}", macro_string);
+        }
+
+        let expanded = unsafe { CStr::from_ptr(obuf) }
+            .to_string_lossy()
+            .into_owned();
+
+        unsafe {
+            librpm_sys::rfree(obuf as *mut _);
+        }
+
+        if rc < 0 {
+            fail!(ErrorKind::Macro, "error expanding macro: {}", macro_string);
+        }
+
+        Ok(expanded)
+    }
+
     #[cfg(feature = "librpm-4-14")]
     /// Delete a macro from this context.
     pub fn pop(&self, name: &str) -> Result<(), Error> {
-        let cstr = CString::new(name).unwrap();
+        let _global_state = GlobalState::lock();
+        let cstr = CString::new(name).map_err(|e| format_err!(ErrorKind::InvalidInput, "{}", e))?;
 
         unsafe {
             librpm_sys::rpmPopMacro(self.0, cstr.as_ptr());
@@ -64,7 +97,8 @@ impl MacroContext {
     #[cfg(not(feature = "librpm-4-14"))]
     /// Delete a macro from this context.
     pub fn delete(&self, name: &str) -> Result<(), Error> {
-        let cstr = CString::new(name).unwrap();
+        let _global_state = GlobalState::lock();
+        let cstr = CString::new(name).map_err(|e| format_err!(ErrorKind::InvalidInput, "{}", e))?;
 
         unsafe {
             librpm_sys::delMacro(self.0, cstr.as_ptr());
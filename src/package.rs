@@ -16,11 +16,47 @@
  */
 
 //! RPM package type: represents `.rpm` files or entries in the RPM database
+
+mod changelog;
+mod dependency;
+mod extract;
+mod nevra;
+mod payload;
+mod rich;
+mod scriptlet;
+mod trigger;
+
+pub use self::{
+    changelog::ChangelogEntry,
+    dependency::{Dependency, DependencyFlags},
+    extract::ExtractOptions,
+    nevra::Nevra,
+    payload::{FileMetadata, Payload, PayloadEntry},
+    rich::RichDep,
+    scriptlet::{Scriptlet, ScriptletFlags, ScriptletKind},
+    trigger::{FileTrigger, Trigger, TriggerKind},
+};
+
+use crate::error::{Error, ErrorKind};
+use crate::internal::{
+    fd::FileDescriptor, header::Header, iterator::MatchIterator, signature::SignatureHeader,
+    tag::Tag,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
 use std::{fmt, time};
+use streaming_iterator::StreamingIterator;
 
 /// RPM packages
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+///
+/// Packages are equal when their metadata is, wherever they were read from:
+/// the database record and file path aren't compared.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Package {
     pub(crate) name: String,
     pub(crate) epoch: Option<i32>,
@@ -31,9 +67,105 @@ pub struct Package {
     pub(crate) summary: String,
     pub(crate) description: String,
     pub(crate) buildtime: i32,
+    pub(crate) url: Option<String>,
+    pub(crate) vendor: Option<String>,
+    pub(crate) packager: Option<String>,
+    pub(crate) buildhost: Option<String>,
+    pub(crate) group: Option<String>,
+    pub(crate) size: Option<u64>,
+    pub(crate) installtime: Option<u32>,
+    pub(crate) sourcerpm: Option<String>,
+    pub(crate) distribution: Option<String>,
+    pub(crate) disttag: Option<String>,
+    pub(crate) os: Option<String>,
+    pub(crate) platform: Option<String>,
+    pub(crate) rpmversion: Option<String>,
+    pub(crate) cookie: Option<String>,
+    pub(crate) payload_format: Option<String>,
+    pub(crate) payload_compressor: Option<String>,
+    pub(crate) optflags: Option<String>,
+    pub(crate) record: Option<u32>,
+    pub(crate) sha1header: Option<String>,
+    pub(crate) sha256header: Option<String>,
+    pub(crate) pkgid: Option<String>,
+    pub(crate) installtid: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) path: Option<PathBuf>,
 }
 
 impl Package {
+    /// Read the package file (`.rpm`) at the given path.
+    ///
+    /// Signatures are checked against the configured keyring, but packages
+    /// signed with unknown keys are still read.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Package, Error> {
+        let path = path.as_ref();
+        let fd = FileDescriptor::open(path, "r.ufdio")?;
+        let header = Header::read_package(&fd, path)?;
+
+        let mut package = header.to_package()?;
+        package.path = Some(path.to_owned());
+        Ok(package)
+    }
+
+    /// Path of the package file, if it was read with `Package::open`
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Read the signature header of this package.
+    ///
+    /// Only packages read with `Package::open` have a signature header.
+    pub fn signature_header(&self) -> Result<SignatureHeader, Error> {
+        match self.path {
+            Some(ref path) => SignatureHeader::open(path),
+            None => fail!(
+                ErrorKind::InvalidInput,
+                "package has no signature header (not read from a file): {}",
+                self
+            ),
+        }
+    }
+
+    /// Open the payload of this package to stream the files in it.
+    ///
+    /// Only packages read with `Package::open` have a payload.
+    pub fn payload(&self) -> Result<Payload, Error> {
+        match self.path {
+            Some(ref path) => Payload::open(path),
+            None => fail!(
+                ErrorKind::InvalidInput,
+                "package has no payload (not read from a file): {}",
+                self
+            ),
+        }
+    }
+
+    /// Extract the files in the payload of this package into `dest`, which
+    /// is created if it doesn't exist.
+    ///
+    /// Files are placed at their installed paths relative to `dest`. Paths
+    /// which would end up outside of it (e.g. containing `..` or leading
+    /// through symlinks) are rejected with an error. The contents of regular
+    /// files are checked against the digests in the header, failing with
+    /// `ErrorKind::BadSignature` if they don't match.
+    ///
+    /// Only packages read with `Package::open` have a payload.
+    pub fn extract_to<P: AsRef<Path>>(
+        &self,
+        dest: P,
+        options: ExtractOptions,
+    ) -> Result<(), Error> {
+        match self.path {
+            Some(ref path) => extract::extract(path, dest.as_ref(), options),
+            None => fail!(
+                ErrorKind::InvalidInput,
+                "package has no payload (not read from a file): {}",
+                self
+            ),
+        }
+    }
+
     /// Name of the package
     pub fn name(&self) -> &str {
         &self.name
@@ -77,6 +209,20 @@ impl Package {
         }
     }
 
+    /// Compare the epoch, version and release of this package with those of
+    /// `other`, using RPM's version ordering (`rpmVersionCompare`).
+    ///
+    /// A missing epoch counts as 0. Names and arches are not compared.
+    ///
+    /// Panics if either NEVR contains null bytes, which is
+    /// only possible for deserialized packages.
+    pub fn compare_evr(&self, other: &Package) -> Ordering {
+        let ours = self.nevr_header().expect("null byte in package NEVR");
+        let theirs = other.nevr_header().expect("null byte in package NEVR");
+
+        unsafe { librpm_sys::rpmVersionCompare(ours.as_ptr(), theirs.as_ptr()) }.cmp(&0)
+    }
+
     /// License of the package
     pub fn license(&self) -> &str {
         &self.license
@@ -97,6 +243,257 @@ impl Package {
         let buildtime = u64::try_from(self.buildtime).expect("negative build time");
         time::SystemTime::UNIX_EPOCH + time::Duration::new(buildtime, 0)
     }
+
+    /// Upstream URL of the package
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    /// Vendor of the package
+    pub fn vendor(&self) -> Option<&str> {
+        self.vendor.as_deref()
+    }
+
+    /// Packager of the package, normally a name and email address
+    pub fn packager(&self) -> Option<&str> {
+        self.packager.as_deref()
+    }
+
+    /// Host the package was built on
+    pub fn buildhost(&self) -> Option<&str> {
+        self.buildhost.as_deref()
+    }
+
+    /// Group of the package (e.g. "System Environment/Base")
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    /// Total size in bytes of the files in the package, once installed
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Time the package was installed, for installed packages
+    pub fn installtime(&self) -> Option<time::SystemTime> {
+        let installtime = u64::from(self.installtime?);
+        Some(time::SystemTime::UNIX_EPOCH + time::Duration::new(installtime, 0))
+    }
+
+    /// File name of the source package this package was built from.
+    ///
+    /// Source packages don't have one.
+    pub fn sourcerpm(&self) -> Option<&str> {
+        self.sourcerpm.as_deref()
+    }
+
+    /// Distribution the package is part of
+    pub fn distribution(&self) -> Option<&str> {
+        self.distribution.as_deref()
+    }
+
+    /// Tag of the distribution the package is part of (`%disttag`)
+    pub fn disttag(&self) -> Option<&str> {
+        self.disttag.as_deref()
+    }
+
+    /// Operating system the package was built for
+    pub fn os(&self) -> Option<&str> {
+        self.os.as_deref()
+    }
+
+    /// Platform (target triplet) the package was built for
+    pub fn platform(&self) -> Option<&str> {
+        self.platform.as_deref()
+    }
+
+    /// Version of rpm the package was built with
+    pub fn rpmversion(&self) -> Option<&str> {
+        self.rpmversion.as_deref()
+    }
+
+    /// Identifier of the build the package came from, shared by all packages
+    /// built from the same source package at the same time
+    pub fn cookie(&self) -> Option<&str> {
+        self.cookie.as_deref()
+    }
+
+    /// Format of the payload (e.g. "cpio")
+    pub fn payload_format(&self) -> Option<&str> {
+        self.payload_format.as_deref()
+    }
+
+    /// Compressor of the payload (e.g. "xz" or "zstd")
+    pub fn payload_compressor(&self) -> Option<&str> {
+        self.payload_compressor.as_deref()
+    }
+
+    /// Compiler flags the package was built with (`%optflags`)
+    pub fn optflags(&self) -> Option<&str> {
+        self.optflags.as_deref()
+    }
+
+    /// Number of the database record the package was read from.
+    ///
+    /// Only packages read from the RPM database have one. It stays the same
+    /// for as long as the package is installed, and can be looked up again
+    /// with `db::find_by_record`.
+    pub fn record(&self) -> Option<u32> {
+        self.record
+    }
+
+    /// Header ID: hex digest of the package header, preferring SHA256 over
+    /// SHA1. Can be looked up again with `db::find_by_hdrid`.
+    pub fn hdrid(&self) -> Option<&str> {
+        self.sha256header().or_else(|| self.sha1header())
+    }
+
+    /// SHA1 digest of the package header, as hex
+    pub fn sha1header(&self) -> Option<&str> {
+        self.sha1header.as_deref()
+    }
+
+    /// SHA256 digest of the package header, as hex
+    pub fn sha256header(&self) -> Option<&str> {
+        self.sha256header.as_deref()
+    }
+
+    /// Package ID: MD5 digest of the package header and payload
+    /// (`SIGMD5`), as hex
+    pub fn pkgid(&self) -> Option<&str> {
+        self.pkgid.as_deref()
+    }
+
+    /// ID of the transaction which installed the package, for installed
+    /// packages. This is the time the transaction started.
+    pub fn installtid(&self) -> Option<u32> {
+        self.installtid
+    }
+
+    /// Changelog of the package, in header order (normally most recent first).
+    ///
+    /// The changelog isn't kept in `Package`, so it's read from the database
+    /// record or package file on each call (see `Package::header`).
+    pub fn changelog(&self) -> Result<Vec<ChangelogEntry>, Error> {
+        Ok(ChangelogEntry::from_header(&self.header()?))
+    }
+
+    /// Changelog entries made at or after the given time, in header order
+    pub fn changelog_since(&self, since: time::SystemTime) -> Result<Vec<ChangelogEntry>, Error> {
+        let mut changelog = self.changelog()?;
+        changelog.retain(|entry| entry.timestamp >= since);
+        Ok(changelog)
+    }
+
+    /// Scriptlets run when the package is installed, removed or verified.
+    ///
+    /// Like the changelog, these are read on each call.
+    pub fn scriptlets(&self) -> Result<Vec<Scriptlet>, Error> {
+        Ok(Scriptlet::from_header(&self.header()?))
+    }
+
+    /// Scriptlet of the given kind, if the package has one
+    pub fn scriptlet(&self, kind: ScriptletKind) -> Result<Option<Scriptlet>, Error> {
+        Ok(self.scriptlets()?.into_iter().find(|s| s.kind == kind))
+    }
+
+    /// Triggers run when other packages are installed or removed.
+    ///
+    /// Like the changelog, these are read on each call.
+    pub fn triggers(&self) -> Result<Vec<Trigger>, Error> {
+        Ok(Trigger::from_header(&self.header()?))
+    }
+
+    /// File triggers, run once per package installing or removing matching
+    /// files (`%filetrigger*`)
+    pub fn file_triggers(&self) -> Result<Vec<FileTrigger>, Error> {
+        Ok(FileTrigger::from_header(&self.header()?))
+    }
+
+    /// Transaction file triggers, run once per transaction installing or
+    /// removing matching files (`%transfiletrigger*`)
+    pub fn transaction_file_triggers(&self) -> Result<Vec<FileTrigger>, Error> {
+        Ok(FileTrigger::transaction_from_header(&self.header()?))
+    }
+
+    /// Read the full header of this package again, from the database record
+    /// or package file it came from.
+    ///
+    /// Packages converted from a standalone `Header` have neither, so this
+    /// fails for them.
+    ///
+    /// Record numbers are reused once packages are removed, and the database
+    /// may have been rebuilt or switched since the package was read. If the
+    /// header under its record has a different digest (or package ID or
+    /// NEVRA, for headers without one), this fails as if it had been removed.
+    pub fn header(&self) -> Result<Header, Error> {
+        if let Some(record) = self.record {
+            let header = MatchIterator::record(record).next().cloned();
+
+            return match header {
+                Some(header)
+                    if header
+                        .to_package()
+                        .map_or(false, |found| self.is_same_header(&found)) =>
+                {
+                    Ok(header)
+                }
+                _ => fail!(
+                    ErrorKind::NotFound,
+                    "package is no longer installed: {}",
+                    self
+                ),
+            };
+        }
+
+        match self.path {
+            Some(ref path) => {
+                let fd = FileDescriptor::open(path, "r.ufdio")?;
+                Header::read_package(&fd, path)
+            }
+            None => fail!(
+                ErrorKind::InvalidInput,
+                "package has no header (not read from a file or the database): {}",
+                self
+            ),
+        }
+    }
+
+    /// Were this package and `other` read from the same header? Compares the
+    /// header digests, or failing that the package IDs or NEVRAs.
+    fn is_same_header(&self, other: &Package) -> bool {
+        if self.sha256header.is_some() || other.sha256header.is_some() {
+            return self.sha256header == other.sha256header;
+        }
+
+        if self.sha1header.is_some() || other.sha1header.is_some() {
+            return self.sha1header == other.sha1header;
+        }
+
+        match (self.pkgid(), other.pkgid()) {
+            (Some(ours), Some(theirs)) => ours == theirs,
+            _ => self.nevra() == other.nevra(),
+        }
+    }
+
+    /// Build a header holding only the name, epoch, version and release of
+    /// this package.
+    ///
+    /// The types match the tags, so this only fails on null bytes, which
+    /// only deserialized packages can contain.
+    pub(crate) fn nevr_header(&self) -> Result<Header, Error> {
+        let mut header = Header::new();
+
+        header.put(Tag::NAME, self.name())?;
+        header.put(Tag::VERSION, self.version())?;
+        header.put(Tag::RELEASE, self.release())?;
+
+        if let Some(epoch) = self.epoch() {
+            header.put(Tag::EPOCH, epoch)?;
+        }
+
+        Ok(header)
+    }
 }
 
 impl std::fmt::Display for Package {
@@ -104,3 +501,147 @@ impl std::fmt::Display for Package {
         write!(f, "{}", self.nevra())
     }
 }
+
+impl PartialEq for Package {
+    fn eq(&self, other: &Package) -> bool {
+        // Listing every field makes adding one without deciding whether it's
+        // compared a compile error
+        let Package {
+            name,
+            epoch,
+            version,
+            release,
+            arch,
+            license,
+            summary,
+            description,
+            buildtime,
+            url,
+            vendor,
+            packager,
+            buildhost,
+            group,
+            size,
+            installtime,
+            sourcerpm,
+            distribution,
+            disttag,
+            os,
+            platform,
+            rpmversion,
+            cookie,
+            payload_format,
+            payload_compressor,
+            optflags,
+            sha1header,
+            sha256header,
+            pkgid,
+            installtid,
+            record: _,
+            path: _,
+        } = self;
+
+        *name == other.name
+            && *epoch == other.epoch
+            && *version == other.version
+            && *release == other.release
+            && *arch == other.arch
+            && *license == other.license
+            && *summary == other.summary
+            && *description == other.description
+            && *buildtime == other.buildtime
+            && *url == other.url
+            && *vendor == other.vendor
+            && *packager == other.packager
+            && *buildhost == other.buildhost
+            && *group == other.group
+            && *size == other.size
+            && *installtime == other.installtime
+            && *sourcerpm == other.sourcerpm
+            && *distribution == other.distribution
+            && *disttag == other.disttag
+            && *os == other.os
+            && *platform == other.platform
+            && *rpmversion == other.rpmversion
+            && *cookie == other.cookie
+            && *payload_format == other.payload_format
+            && *payload_compressor == other.payload_compressor
+            && *optflags == other.optflags
+            && *sha1header == other.sha1header
+            && *sha256header == other.sha256header
+            && *pkgid == other.pkgid
+            && *installtid == other.installtid
+    }
+}
+
+impl Eq for Package {}
+
+impl Hash for Package {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let Package {
+            name,
+            epoch,
+            version,
+            release,
+            arch,
+            license,
+            summary,
+            description,
+            buildtime,
+            url,
+            vendor,
+            packager,
+            buildhost,
+            group,
+            size,
+            installtime,
+            sourcerpm,
+            distribution,
+            disttag,
+            os,
+            platform,
+            rpmversion,
+            cookie,
+            payload_format,
+            payload_compressor,
+            optflags,
+            sha1header,
+            sha256header,
+            pkgid,
+            installtid,
+            record: _,
+            path: _,
+        } = self;
+
+        name.hash(state);
+        epoch.hash(state);
+        version.hash(state);
+        release.hash(state);
+        arch.hash(state);
+        license.hash(state);
+        summary.hash(state);
+        description.hash(state);
+        buildtime.hash(state);
+        url.hash(state);
+        vendor.hash(state);
+        packager.hash(state);
+        buildhost.hash(state);
+        group.hash(state);
+        size.hash(state);
+        installtime.hash(state);
+        sourcerpm.hash(state);
+        distribution.hash(state);
+        disttag.hash(state);
+        os.hash(state);
+        platform.hash(state);
+        rpmversion.hash(state);
+        cookie.hash(state);
+        payload_format.hash(state);
+        payload_compressor.hash(state);
+        optflags.hash(state);
+        sha1header.hash(state);
+        sha256header.hash(state);
+        pkgid.hash(state);
+        installtid.hash(state);
+    }
+}
@@ -0,0 +1,82 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Package changelogs
+
+use crate::internal::{header::Header, tag::Tag};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+/// Entry in the changelog of a package
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChangelogEntry {
+    pub(crate) timestamp: SystemTime,
+    pub(crate) author: String,
+    pub(crate) text: String,
+}
+
+impl ChangelogEntry {
+    /// Time of the change (RPM only records the day)
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// Author of the change, typically `Name <email> - version-release`
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    /// Description of the change
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Read all changelog entries from a header, in header order
+    pub(crate) fn from_header(header: &Header) -> Vec<Self> {
+        let times = header.get(Tag::CHANGELOGTIME);
+        let times = times
+            .as_ref()
+            .and_then(|d| d.as_int32_array())
+            .unwrap_or_default();
+
+        let authors = header.get(Tag::CHANGELOGNAME);
+        let authors = authors
+            .as_ref()
+            .and_then(|d| d.as_str_array())
+            .unwrap_or_default();
+
+        let texts = header.get(Tag::CHANGELOGTEXT);
+        let texts = texts
+            .as_ref()
+            .and_then(|d| d.as_str_array())
+            .unwrap_or_default();
+
+        times
+            .iter()
+            .zip(authors)
+            .zip(texts)
+            .map(|((&time, author), text)| ChangelogEntry {
+                // Header timestamps are unsigned 32-bit values
+                timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(u64::from(time as u32)),
+                author: author.clone(),
+                text: text.clone(),
+            })
+            .collect()
+    }
+}
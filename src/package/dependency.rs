@@ -0,0 +1,288 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Dependencies: named capabilities with an optional version range
+
+use super::Package;
+use crate::error::{Error, ErrorKind};
+use crate::internal::{ds::DependencySet, tag::Tag};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Dependency sense flags (`rpmsenseFlags` in librpm)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DependencyFlags(u32);
+
+impl DependencyFlags {
+    /// No version comparison
+    pub const ANY: DependencyFlags = DependencyFlags(librpm_sys::rpmsenseFlags_e_RPMSENSE_ANY);
+
+    /// Version must be less than the given one
+    pub const LESS: DependencyFlags = DependencyFlags(librpm_sys::rpmsenseFlags_e_RPMSENSE_LESS);
+
+    /// Version must be greater than the given one
+    pub const GREATER: DependencyFlags =
+        DependencyFlags(librpm_sys::rpmsenseFlags_e_RPMSENSE_GREATER);
+
+    /// Version must be equal to the given one
+    pub const EQUAL: DependencyFlags = DependencyFlags(librpm_sys::rpmsenseFlags_e_RPMSENSE_EQUAL);
+
+    /// Mask of the version comparison flags
+    pub const SENSEMASK: DependencyFlags = DependencyFlags(
+        librpm_sys::rpmsenseFlags_e_RPMSENSE_LESS
+            | librpm_sys::rpmsenseFlags_e_RPMSENSE_GREATER
+            | librpm_sys::rpmsenseFlags_e_RPMSENSE_EQUAL,
+    );
+
+    /// Legacy prerequisite (`PreReq:`)
+    pub const PREREQ: DependencyFlags =
+        DependencyFlags(librpm_sys::rpmsenseFlags_e_RPMSENSE_PREREQ);
+
+    /// Trigger run when the condition is installed (`%triggerin`)
+    pub const TRIGGERIN: DependencyFlags =
+        DependencyFlags(librpm_sys::rpmsenseFlags_e_RPMSENSE_TRIGGERIN);
+
+    /// Trigger run when the condition is removed (`%triggerun`)
+    pub const TRIGGERUN: DependencyFlags =
+        DependencyFlags(librpm_sys::rpmsenseFlags_e_RPMSENSE_TRIGGERUN);
+
+    /// Trigger run after the condition is removed (`%triggerpostun`)
+    pub const TRIGGERPOSTUN: DependencyFlags =
+        DependencyFlags(librpm_sys::rpmsenseFlags_e_RPMSENSE_TRIGGERPOSTUN);
+
+    /// Trigger run before the condition is installed (`%triggerprein`)
+    pub const TRIGGERPREIN: DependencyFlags =
+        DependencyFlags(librpm_sys::rpmsenseFlags_e_RPMSENSE_TRIGGERPREIN);
+
+    /// Dependency which may be left unsatisfied (e.g. `Requires(missingok)`)
+    pub const MISSINGOK: DependencyFlags =
+        DependencyFlags(librpm_sys::rpmsenseFlags_e_RPMSENSE_MISSINGOK);
+
+    /// Dependency on an internal librpm feature (`rpmlib(...)`)
+    pub const RPMLIB: DependencyFlags =
+        DependencyFlags(librpm_sys::rpmsenseFlags_e_RPMSENSE_RPMLIB);
+
+    /// Create flags from their raw value in the header
+    pub fn from_bits(bits: u32) -> Self {
+        DependencyFlags(bits)
+    }
+
+    /// Raw value of the flags
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Are all of the given flags set?
+    pub fn contains(self, other: DependencyFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Only the version comparison flags (`LESS`, `GREATER` and `EQUAL`)
+    pub fn sense(self) -> DependencyFlags {
+        DependencyFlags(self.0 & Self::SENSEMASK.0)
+    }
+
+    /// Version comparison flags for the given operator (e.g. `>=`)
+    pub fn from_operator(operator: &str) -> Option<Self> {
+        let flags = match operator {
+            "<" => Self::LESS.0,
+            "<=" => Self::LESS.0 | Self::EQUAL.0,
+            "=" | "==" => Self::EQUAL.0,
+            ">=" => Self::GREATER.0 | Self::EQUAL.0,
+            ">" => Self::GREATER.0,
+            _ => return None,
+        };
+
+        Some(DependencyFlags(flags))
+    }
+
+    /// Comparison operator for the version comparison flags, if any
+    pub fn operator(self) -> Option<&'static str> {
+        let less = self.contains(Self::LESS);
+        let greater = self.contains(Self::GREATER);
+        let equal = self.contains(Self::EQUAL);
+
+        match (less, greater, equal) {
+            (true, false, false) => Some("<"),
+            (true, false, true) => Some("<="),
+            (false, false, true) => Some("="),
+            (false, true, true) => Some(">="),
+            (false, true, false) => Some(">"),
+            _ => None,
+        }
+    }
+}
+
+/// Dependency on a capability, e.g. `glibc >= 2.17`
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Dependency {
+    pub(crate) name: String,
+    pub(crate) flags: DependencyFlags,
+    pub(crate) version: Option<String>,
+}
+
+impl Dependency {
+    /// Create a new dependency.
+    ///
+    /// Fails if the name or version contains null bytes, which librpm can't
+    /// represent.
+    pub fn new<S: Into<String>>(
+        name: S,
+        flags: DependencyFlags,
+        version: Option<String>,
+    ) -> Result<Self, Error> {
+        let name = name.into();
+
+        if name.contains('\0') || version.as_deref().map_or(false, |v| v.contains('\0')) {
+            fail!(
+                ErrorKind::InvalidInput,
+                "null byte in dependency: {:?}",
+                name
+            );
+        }
+
+        Ok(Dependency {
+            name,
+            flags,
+            version: version.filter(|v| !v.is_empty()),
+        })
+    }
+
+    /// Parse a dependency in the form used in spec files, i.e. a capability
+    /// name optionally followed by a comparison operator and version, e.g.
+    /// `glibc`, `glibc >= 2.17` or `libssl.so.3()(64bit)`.
+    ///
+    /// Like `Dependency::new`, this rejects null bytes.
+    pub fn parse(dependency: &str) -> Result<Dependency, Error> {
+        let mut parts = dependency.split_whitespace();
+
+        let name = match parts.next() {
+            Some(name) => name,
+            None => fail!(ErrorKind::InvalidInput, "empty dependency"),
+        };
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (None, _, _) => Dependency::new(name, DependencyFlags::ANY, None),
+            (Some(operator), Some(version), None) => {
+                match DependencyFlags::from_operator(operator) {
+                    Some(flags) => Dependency::new(name, flags, Some(version.to_owned())),
+                    None => fail!(
+                        ErrorKind::InvalidInput,
+                        "invalid operator in dependency: {}",
+                        dependency
+                    ),
+                }
+            }
+            _ => fail!(
+                ErrorKind::InvalidInput,
+                "invalid dependency: {}",
+                dependency
+            ),
+        }
+    }
+
+    /// Name of the capability
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sense flags of the dependency
+    pub fn flags(&self) -> DependencyFlags {
+        self.flags
+    }
+
+    /// Version (EVR) the capability is compared against, if any
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Do this dependency and `other` have the same name and overlapping
+    /// version ranges? (`rpmdsCompare`)
+    ///
+    /// Versions are compared with librpm's rules: a side without a release
+    /// matches any release, and a side without an epoch has epoch 0. E.g.
+    /// `glibc >= 2.34` overlaps `glibc = 2.34-60.el9`.
+    ///
+    /// Fails if either dependency contains null bytes, which is only
+    /// possible for deserialized ones.
+    pub fn overlaps(&self, other: &Dependency) -> Result<bool, Error> {
+        let ours = DependencySet::single(Tag::REQUIRENAME, self)?;
+        let theirs = DependencySet::single(Tag::PROVIDENAME, other)?;
+        Ok(ours.overlaps(&theirs))
+    }
+
+    /// Like `overlaps`, but a side without an epoch matches any epoch of the
+    /// other side (epoch promotion, as done by rpm before 4.16).
+    ///
+    /// librpm no longer promotes epochs itself, so when only one side has an
+    /// epoch, it's set aside and the rest of the versions are compared.
+    pub fn overlaps_promoting_epoch(&self, other: &Dependency) -> Result<bool, Error> {
+        match (self.without_epoch(), other.without_epoch()) {
+            (Some(ours), None) if other.version.is_some() => ours.overlaps(other),
+            (None, Some(theirs)) if self.version.is_some() => self.overlaps(&theirs),
+            _ => self.overlaps(other),
+        }
+    }
+
+    /// Is this dependency, taken as a requirement, satisfied by the name,
+    /// epoch, version and release of `package`? (`rpmdsNVRMatchesDep`)
+    ///
+    /// Only the package's own NEVR is considered, not its other provides.
+    pub fn is_satisfied_by(&self, package: &Package) -> Result<bool, Error> {
+        let required = DependencySet::single(Tag::REQUIRENAME, self)?;
+        Ok(required.matches_nevr(&package.nevr_header()?))
+    }
+
+    /// This dependency with the epoch removed from its version, if it has one
+    fn without_epoch(&self) -> Option<Dependency> {
+        let (epoch, version) = self.version.as_deref()?.split_once(':')?;
+
+        if epoch.is_empty() || !epoch.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        Some(Dependency {
+            name: self.name.clone(),
+            flags: self.flags,
+            version: Some(version.to_owned()),
+        })
+    }
+}
+
+impl FromStr for Dependency {
+    type Err = Error;
+
+    fn from_str(dependency: &str) -> Result<Dependency, Error> {
+        Dependency::parse(dependency)
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+
+        if let (Some(op), Some(version)) = (self.flags.operator(), &self.version) {
+            write!(f, " {} {}", op, version)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,364 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Extracting package payloads into a directory
+
+use super::payload::{FileMetadata, Payload, PayloadEntry};
+use crate::error::{Error, ErrorKind};
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{symlink, OpenOptionsExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::path::{Component, Path, PathBuf};
+
+/// Options for `Package::extract_to`
+///
+/// File modes, symlinks and hardlinks are always restored.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ExtractOptions {
+    /// Set the owner and group of extracted files to those recorded in the
+    /// package, resolved against the host's `/etc/passwd` and `/etc/group`.
+    /// This normally requires running as root.
+    pub preserve_ownership: bool,
+
+    /// Set the modification times of extracted files to those recorded in
+    /// the package, instead of the time of extraction.
+    pub preserve_mtimes: bool,
+}
+
+/// Extract the payload of the package file at `package` into `dest`
+pub(crate) fn extract(package: &Path, dest: &Path, options: ExtractOptions) -> Result<(), Error> {
+    let mut payload = Payload::open(package)?;
+    let mut extractor = Extractor::new(dest, options)?;
+
+    while let Some(mut entry) = payload.next_entry()? {
+        extractor.extract(&mut entry)?;
+    }
+
+    extractor.finish()
+}
+
+/// State kept while extracting a payload
+struct Extractor {
+    /// Directory files are extracted into
+    dest: PathBuf,
+
+    /// Extraction options
+    options: ExtractOptions,
+
+    /// Owner database, loaded when ownership is preserved
+    owners: Owners,
+
+    /// Directories, whose metadata is applied once all files are extracted
+    dirs: Vec<(PathBuf, FileMetadata)>,
+
+    /// Extracted files with further hardlinks, by inode
+    links: HashMap<u32, PathBuf>,
+
+    /// Hardlinks whose contents haven't been seen yet, by inode
+    pending_links: HashMap<u32, Vec<PathBuf>>,
+}
+
+impl Extractor {
+    fn new(dest: &Path, options: ExtractOptions) -> Result<Self, Error> {
+        fs::create_dir_all(dest).map_err(|e| io_error(dest, e))?;
+
+        let owners = if options.preserve_ownership {
+            Owners::load()
+        } else {
+            Owners::default()
+        };
+
+        Ok(Extractor {
+            dest: dest.to_owned(),
+            options,
+            owners,
+            dirs: vec![],
+            links: HashMap::new(),
+            pending_links: HashMap::new(),
+        })
+    }
+
+    /// Extract a single archive entry
+    fn extract(&mut self, payload_entry: &mut PayloadEntry<'_>) -> Result<(), Error> {
+        let entry = payload_entry.metadata().clone();
+        let path = self.prepare_path(entry.path())?;
+
+        if entry.is_dir() {
+            match fs::symlink_metadata(&path) {
+                Ok(ref meta) if meta.is_dir() => (),
+                Ok(_) => {
+                    remove_existing(&path)?;
+                    fs::create_dir(&path).map_err(|e| io_error(&path, e))?;
+                }
+                Err(_) => fs::create_dir(&path).map_err(|e| io_error(&path, e))?,
+            }
+
+            // Applied at the end, so read-only directories can be filled
+            self.dirs.push((path, entry));
+        } else if entry.is_symlink() {
+            let target = match entry.link_target() {
+                Some(target) => target,
+                None => fail!(
+                    ErrorKind::Header,
+                    "symlink without target: {}",
+                    entry.path()
+                ),
+            };
+
+            remove_existing(&path)?;
+            symlink(target, &path).map_err(|e| io_error(&path, e))?;
+            self.set_owner(&path, &entry)?;
+        } else if entry.is_file() && entry.nlink() > 1 && !entry.has_content() {
+            // The contents of a set of hardlinks are stored with only one of
+            // them, which is normally the last one
+            remove_existing(&path)?;
+
+            if let Some(original) = self.links.get(&entry.inode()) {
+                fs::hard_link(original, &path).map_err(|e| io_error(&path, e))?;
+            } else {
+                self.pending_links
+                    .entry(entry.inode())
+                    .or_default()
+                    .push(path);
+            }
+        } else if entry.is_file() {
+            remove_existing(&path)?;
+
+            let file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(&path)
+                .map_err(|e| io_error(&path, e))?;
+
+            // Checks the contents against their digest, as rpm does
+            payload_entry.read_to_file(&file).map_err(|e| {
+                let msg = e.message().unwrap_or("error reading package payload");
+                Error::new(e.kind(), Some(format!("{}: {}", path.display(), msg)))
+            })?;
+            self.set_metadata(&path, &file, &entry)?;
+
+            if entry.nlink() > 1 {
+                for link in self
+                    .pending_links
+                    .remove(&entry.inode())
+                    .unwrap_or_default()
+                {
+                    fs::hard_link(&path, &link).map_err(|e| io_error(&link, e))?;
+                }
+
+                self.links.insert(entry.inode(), path);
+            }
+        }
+
+        // Device nodes, FIFOs and sockets can't be safely recreated by an
+        // unprivileged extractor, so they are skipped
+        Ok(())
+    }
+
+    /// Apply directory metadata and check all hardlinks were resolved
+    fn finish(self) -> Result<(), Error> {
+        if let Some(paths) = self.pending_links.values().next() {
+            fail!(
+                ErrorKind::Io,
+                "missing contents for hardlinked file: {}",
+                paths[0].display()
+            );
+        }
+
+        // Deepest directories first, so setting their metadata doesn't
+        // change the mtimes of their parents or lock us out of them
+        for (path, entry) in self.dirs.iter().rev() {
+            let dir = File::open(path).map_err(|e| io_error(path, e))?;
+            self.set_metadata(path, &dir, entry)?;
+        }
+
+        Ok(())
+    }
+
+    /// Map the absolute path of an archive entry to a path under `dest`,
+    /// creating its parent directories.
+    ///
+    /// Paths containing `..`, or leading through symlinks (which a previous
+    /// entry could have pointed outside of `dest`) are rejected.
+    fn prepare_path(&self, archive_path: &str) -> Result<PathBuf, Error> {
+        let mut relative = PathBuf::new();
+
+        for component in Path::new(archive_path).components() {
+            match component {
+                Component::Normal(name) => relative.push(name),
+                Component::RootDir | Component::CurDir => (),
+                Component::ParentDir | Component::Prefix(_) => {
+                    fail!(
+                        ErrorKind::InvalidInput,
+                        "unsafe path in package: {}",
+                        archive_path
+                    )
+                }
+            }
+        }
+
+        let file_name = match relative.file_name() {
+            Some(name) => name.to_owned(),
+            None => fail!(
+                ErrorKind::InvalidInput,
+                "unsafe path in package: {}",
+                archive_path
+            ),
+        };
+
+        let mut path = self.dest.clone();
+
+        if let Some(parent) = relative.parent() {
+            for component in parent.components() {
+                path.push(component);
+
+                match fs::symlink_metadata(&path) {
+                    Ok(ref meta) if meta.is_dir() => (),
+                    Ok(_) => fail!(
+                        ErrorKind::InvalidInput,
+                        "path in package leads through a non-directory: {}",
+                        archive_path
+                    ),
+                    Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                        fs::create_dir(&path).map_err(|e| io_error(&path, e))?
+                    }
+                    Err(e) => return Err(io_error(&path, e)),
+                }
+            }
+        }
+
+        path.push(file_name);
+        Ok(path)
+    }
+
+    /// Set ownership, permissions and mtime of an extracted file
+    fn set_metadata(&self, path: &Path, file: &File, entry: &FileMetadata) -> Result<(), Error> {
+        // Changing the owner clears setuid and setgid bits, so do it first
+        self.set_owner(path, entry)?;
+
+        let permissions = fs::Permissions::from_mode(entry.permissions());
+        file.set_permissions(permissions)
+            .map_err(|e| io_error(path, e))?;
+
+        if self.options.preserve_mtimes {
+            set_mtime(file, entry).map_err(|e| io_error(path, e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Set the owner and group of an extracted file, if requested
+    fn set_owner(&self, path: &Path, entry: &FileMetadata) -> Result<(), Error> {
+        if !self.options.preserve_ownership {
+            return Ok(());
+        }
+
+        // Like rpm itself, fall back to root for unknown users and groups
+        let uid = self.owners.users.get(entry.user()).cloned().unwrap_or(0);
+        let gid = self.owners.groups.get(entry.group()).cloned().unwrap_or(0);
+
+        let path_cstr = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io_error(path, io::Error::new(io::ErrorKind::InvalidInput, e)))?;
+
+        if unsafe { libc::lchown(path_cstr.as_ptr(), uid, gid) } != 0 {
+            return Err(io_error(path, io::Error::last_os_error()));
+        }
+
+        Ok(())
+    }
+}
+
+/// User and group IDs by name
+#[derive(Default)]
+struct Owners {
+    users: HashMap<String, u32>,
+    groups: HashMap<String, u32>,
+}
+
+impl Owners {
+    /// Load the host's user and group databases
+    fn load() -> Self {
+        Owners {
+            users: read_id_file(Path::new("/etc/passwd")),
+            groups: read_id_file(Path::new("/etc/group")),
+        }
+    }
+}
+
+/// Read a `name:password:id:...` file like `/etc/passwd` or `/etc/group`
+fn read_id_file(path: &Path) -> HashMap<String, u32> {
+    let mut ids = HashMap::new();
+
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return ids,
+    };
+
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let mut fields = line.split(':');
+
+        if let (Some(name), Some(id)) = (fields.next(), fields.nth(1)) {
+            if let Ok(id) = id.parse() {
+                ids.entry(name.to_owned()).or_insert(id);
+            }
+        }
+    }
+
+    ids
+}
+
+/// Set the modification time of an extracted file, leaving its access time
+fn set_mtime(file: &File, entry: &FileMetadata) -> io::Result<()> {
+    let times = [
+        libc::timespec {
+            tv_sec: 0,
+            tv_nsec: libc::UTIME_OMIT,
+        },
+        libc::timespec {
+            tv_sec: entry.mtime as libc::time_t,
+            tv_nsec: 0,
+        },
+    ];
+
+    if unsafe { libc::futimens(file.as_raw_fd(), times.as_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Remove whatever is at `path`, without following symlinks
+fn remove_existing(path: &Path) -> Result<(), Error> {
+    let result = match fs::symlink_metadata(path) {
+        Ok(ref meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => Err(e),
+    };
+
+    result.map_err(|e| io_error(path, e))
+}
+
+/// Wrap an I/O error with the path it occurred on
+fn io_error(path: &Path, err: io::Error) -> Error {
+    format_err!(ErrorKind::Io, "{}: {}", path.display(), err).with_source(err)
+}
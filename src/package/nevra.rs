@@ -0,0 +1,256 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Package labels: name, epoch, version, release and arch
+
+use crate::error::{Error, ErrorKind};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Architectures recognized at the end of a label which isn't a file name
+/// (from the `arch_canon` table of the default rpmrc)
+const KNOWN_ARCHES: &[&str] = &[
+    "noarch",
+    "src",
+    "nosrc",
+    "i386",
+    "i486",
+    "i586",
+    "i686",
+    "athlon",
+    "geode",
+    "pentium3",
+    "pentium4",
+    "x86_64",
+    "amd64",
+    "ia32e",
+    "em64t",
+    "x86_64_v2",
+    "x86_64_v3",
+    "x86_64_v4",
+    "aarch64",
+    "armv5tel",
+    "armv6l",
+    "armv6hl",
+    "armv7l",
+    "armv7hl",
+    "armv7hnl",
+    "armv8l",
+    "ppc",
+    "ppc64",
+    "ppc64le",
+    "ppc64p7",
+    "ppc64pseries",
+    "ppc64iseries",
+    "s390",
+    "s390x",
+    "ia64",
+    "alpha",
+    "sparc",
+    "sparcv9",
+    "sparc64",
+    "mips",
+    "mipsel",
+    "mips64",
+    "mips64el",
+    "riscv64",
+    "loongarch64",
+];
+
+/// Package label in the form `name-[epoch:]version-release.arch`, where all
+/// parts but the name are optional.
+///
+/// Labels are ambiguous, as names and versions may contain `-` and `.`. When
+/// parsing, parts are split off the end of the label only if they look like
+/// what they should be:
+///
+/// - the arch must be a known architecture (e.g. `x86_64` or `noarch`)
+/// - the version must start with a digit, after the optional epoch
+///
+/// so e.g. `openssl-libs` is a name, whereas `bash-5.1.8` is a name and
+/// version. Package file names (`*.rpm`) are always split in full.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Nevra {
+    pub(crate) name: String,
+    pub(crate) epoch: Option<i32>,
+    pub(crate) version: Option<String>,
+    pub(crate) release: Option<String>,
+    pub(crate) arch: Option<String>,
+}
+
+impl Nevra {
+    /// Parse a label, or the file name of a package (e.g.
+    /// `bash-5.1.8-6.el9.x86_64.rpm` or `bash-5.1.8-6.el9.src.rpm`)
+    pub fn parse(label: &str) -> Result<Nevra, Error> {
+        match label.strip_suffix(".rpm") {
+            Some(file_name) => Self::parse_file_name(label, file_name),
+            None => Self::parse_label(label),
+        }
+    }
+
+    /// Name of the package
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Epoch of the package, if given
+    pub fn epoch(&self) -> Option<i32> {
+        self.epoch
+    }
+
+    /// Version of the package, if given
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Release of the package, if given
+    pub fn release(&self) -> Option<&str> {
+        self.release.as_deref()
+    }
+
+    /// Arch of the package, if given
+    pub fn arch(&self) -> Option<&str> {
+        self.arch.as_deref()
+    }
+
+    /// Parse `name-[epoch:]version-release.arch`, all of which are required
+    fn parse_file_name(label: &str, file_name: &str) -> Result<Nevra, Error> {
+        let invalid = || {
+            format_err!(
+                ErrorKind::InvalidInput,
+                "invalid package file name: {}",
+                label
+            )
+        };
+
+        let (rest, arch) = file_name.rsplit_once('.').ok_or_else(invalid)?;
+        let (rest, release) = rest.rsplit_once('-').ok_or_else(invalid)?;
+        let (name, evr) = rest.rsplit_once('-').ok_or_else(invalid)?;
+        let (epoch, version) = split_epoch(evr).ok_or_else(invalid)?;
+
+        if [name, version, release, arch]
+            .iter()
+            .any(|part| part.is_empty())
+        {
+            return Err(invalid());
+        }
+
+        Ok(Nevra {
+            name: name.to_owned(),
+            epoch,
+            version: Some(version.to_owned()),
+            release: Some(release.to_owned()),
+            arch: Some(arch.to_owned()),
+        })
+    }
+
+    /// Parse `name-[epoch:]version-release.arch` with optional parts
+    fn parse_label(label: &str) -> Result<Nevra, Error> {
+        let invalid = || format_err!(ErrorKind::InvalidInput, "invalid package label: {}", label);
+
+        let (rest, arch) = match label.rsplit_once('.') {
+            Some((rest, arch)) if KNOWN_ARCHES.contains(&arch) => (rest, Some(arch)),
+            _ => (label, None),
+        };
+
+        let mut nevra = Nevra {
+            name: rest.to_owned(),
+            epoch: None,
+            version: None,
+            release: None,
+            arch: arch.map(str::to_owned),
+        };
+
+        // Try `name-evr-release` first, then `name-evr`
+        let candidates = rest
+            .rsplit_once('-')
+            .and_then(|(left, release)| {
+                left.rsplit_once('-')
+                    .map(|(name, evr)| (name, evr, Some(release)))
+            })
+            .into_iter()
+            .chain(rest.rsplit_once('-').map(|(name, evr)| (name, evr, None)));
+
+        for (name, evr, release) in candidates {
+            let (epoch, version) = match split_epoch(evr) {
+                Some(parts) => parts,
+                None => continue,
+            };
+
+            let looks_like_version = version.starts_with(|c: char| c.is_ascii_digit());
+
+            if !name.is_empty() && looks_like_version && release != Some("") {
+                nevra.name = name.to_owned();
+                nevra.epoch = epoch;
+                nevra.version = Some(version.to_owned());
+                nevra.release = release.map(str::to_owned);
+                break;
+            }
+        }
+
+        if nevra.name.is_empty() || nevra.name.contains(':') {
+            return Err(invalid());
+        }
+
+        Ok(nevra)
+    }
+}
+
+impl FromStr for Nevra {
+    type Err = Error;
+
+    fn from_str(label: &str) -> Result<Nevra, Error> {
+        Nevra::parse(label)
+    }
+}
+
+impl fmt::Display for Nevra {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+
+        if let Some(version) = &self.version {
+            f.write_str("-")?;
+
+            if let Some(epoch) = self.epoch {
+                write!(f, "{}:", epoch)?;
+            }
+
+            f.write_str(version)?;
+        }
+
+        if let Some(release) = &self.release {
+            write!(f, "-{}", release)?;
+        }
+
+        if let Some(arch) = &self.arch {
+            write!(f, ".{}", arch)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Split `[epoch:]version` into its parts, if the epoch is valid
+fn split_epoch(evr: &str) -> Option<(Option<i32>, &str)> {
+    match evr.split_once(':') {
+        Some((epoch, version)) => Some((Some(epoch.parse().ok()?), version)),
+        None => Some((None, evr)),
+    }
+}
@@ -0,0 +1,216 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Streaming access to the files in package payloads
+
+use crate::error::Error;
+use crate::internal::payload::PayloadReader;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// File type bits of `st_mode`
+const S_IFMT: u32 = 0o170_000;
+
+/// File type of directories
+const S_IFDIR: u32 = 0o040_000;
+
+/// File type of regular files
+const S_IFREG: u32 = 0o100_000;
+
+/// File type of symbolic links
+const S_IFLNK: u32 = 0o120_000;
+
+/// Permission bits of `st_mode` (including setuid, setgid and sticky)
+const PERMISSION_BITS: u32 = 0o7777;
+
+/// Payload of a package file: the (compressed) archive of its files
+///
+/// The payload can only be read front to back, once. Nothing is written to
+/// disk while reading it.
+///
+/// # Example
+///
+/// Reading a single file out of a package:
+///
+/// ```no_run
+/// use librpm::Package;
+/// use std::io::Read;
+///
+/// let package = Package::open("example-1.0-1.x86_64.rpm").unwrap();
+/// let mut payload = package.payload().unwrap();
+///
+/// while let Some(mut entry) = payload.next_entry().unwrap() {
+///     if entry.metadata().path() == "/usr/lib/os-release" {
+///         let mut contents = String::new();
+///         entry.read_to_string(&mut contents).unwrap();
+///         println!("{}", contents);
+///     }
+/// }
+/// ```
+pub struct Payload(PayloadReader);
+
+impl Payload {
+    /// Open the payload of the package file at the given path.
+    ///
+    /// The payload is decompressed with the compressor declared by the
+    /// package (gzip, bzip2, xz, lzma or zstd, as supported by librpmio).
+    /// Packages declaring any other compressor are rejected with
+    /// `ErrorKind::Header`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        PayloadReader::open(path.as_ref()).map(Payload)
+    }
+
+    /// Name of the compressor used for this payload, e.g. `xz`
+    pub fn compressor(&self) -> &str {
+        self.0.compressor()
+    }
+
+    /// Advance to the next file in the archive, returning `None` at its end.
+    ///
+    /// Any contents of the previous entry which weren't read are skipped.
+    pub fn next_entry(&mut self) -> Result<Option<PayloadEntry<'_>>, Error> {
+        let metadata = match self.0.next_entry()? {
+            Some(metadata) => metadata,
+            None => return Ok(None),
+        };
+
+        Ok(Some(PayloadEntry {
+            metadata,
+            reader: &mut self.0,
+        }))
+    }
+}
+
+/// File in a package payload, whose contents can be read with `io::Read`
+pub struct PayloadEntry<'a> {
+    /// Metadata of the file
+    metadata: FileMetadata,
+
+    /// Payload positioned at the contents of this file
+    reader: &'a mut PayloadReader,
+}
+
+impl PayloadEntry<'_> {
+    /// Metadata of this file
+    pub fn metadata(&self) -> &FileMetadata {
+        &self.metadata
+    }
+
+    /// Write the contents of this file to `file`, checking them against the
+    /// digest in the package header
+    pub(crate) fn read_to_file(&mut self, file: &File) -> Result<(), Error> {
+        self.reader.read_to_file(file)
+    }
+}
+
+impl io::Read for PayloadEntry<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+/// Metadata of a file in a package payload
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileMetadata {
+    pub(crate) path: String,
+    pub(crate) mode: u32,
+    pub(crate) size: u64,
+    pub(crate) link_target: Option<String>,
+    pub(crate) user: String,
+    pub(crate) group: String,
+    pub(crate) mtime: u32,
+    pub(crate) nlink: u32,
+    pub(crate) inode: u32,
+    pub(crate) has_content: bool,
+}
+
+impl FileMetadata {
+    /// Absolute path the file is installed to
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// File type and permission bits (`st_mode`)
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    /// Permission bits, including setuid, setgid and sticky
+    pub fn permissions(&self) -> u32 {
+        self.mode & PERMISSION_BITS
+    }
+
+    /// Is this a directory?
+    pub fn is_dir(&self) -> bool {
+        self.mode & S_IFMT == S_IFDIR
+    }
+
+    /// Is this a regular file?
+    pub fn is_file(&self) -> bool {
+        self.mode & S_IFMT == S_IFREG
+    }
+
+    /// Is this a symbolic link?
+    pub fn is_symlink(&self) -> bool {
+        self.mode & S_IFMT == S_IFLNK
+    }
+
+    /// Size of the file in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Target of the symlink, if this is one
+    pub fn link_target(&self) -> Option<&str> {
+        self.link_target.as_deref()
+    }
+
+    /// Name of the user owning the file
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// Name of the group owning the file
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    /// Modification time of the file
+    pub fn mtime(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(u64::from(self.mtime))
+    }
+
+    /// Number of hardlinks to this file in the package
+    pub fn nlink(&self) -> u32 {
+        self.nlink
+    }
+
+    /// Inode number, shared by all hardlinks to the same file
+    pub fn inode(&self) -> u32 {
+        self.inode
+    }
+
+    /// Are the contents of the file stored with this entry?
+    ///
+    /// For a set of hardlinks, the contents are stored with only one of them
+    /// and reading the others yields no data.
+    pub fn has_content(&self) -> bool {
+        self.has_content
+    }
+}
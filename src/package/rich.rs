@@ -0,0 +1,463 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Rich (boolean) dependencies, e.g. `(foo >= 1.0 if bar)`
+
+use super::dependency::{Dependency, DependencyFlags};
+use crate::error::{Error, ErrorKind};
+use crate::internal::{ds::DependencySet, tag::Tag};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+use std::str::FromStr;
+use std::{slice, str};
+
+/// Rich dependency: a boolean expression over simple dependencies
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RichDep {
+    /// Simple dependency, e.g. `foo >= 1.0`
+    Single(Dependency),
+
+    /// All of the operands are required (`and`)
+    And(Vec<RichDep>),
+
+    /// Any of the operands is required (`or`)
+    Or(Vec<RichDep>),
+
+    /// All of the operands are required, from the same package (`with`)
+    With(Vec<RichDep>),
+
+    /// The first operand is required, from a package not also matching the
+    /// second (`without`)
+    Without(Box<RichDep>, Box<RichDep>),
+
+    /// `then` is required if `condition` is met, and otherwise `otherwise`
+    /// is, if given (`if` and `else`)
+    If {
+        /// Operand required if the condition is met
+        then: Box<RichDep>,
+
+        /// Condition
+        condition: Box<RichDep>,
+
+        /// Operand required if the condition is not met
+        otherwise: Option<Box<RichDep>>,
+    },
+
+    /// `then` is required unless `condition` is met, in which case
+    /// `otherwise` is, if given (`unless` and `else`)
+    Unless {
+        /// Operand required unless the condition is met
+        then: Box<RichDep>,
+
+        /// Condition
+        condition: Box<RichDep>,
+
+        /// Operand required if the condition is met
+        otherwise: Option<Box<RichDep>>,
+    },
+}
+
+impl RichDep {
+    /// Parse a rich dependency with librpm (`rpmrichParse`), e.g.
+    /// `(foo >= 1.0 if bar)` or `(a or (b and c))`.
+    ///
+    /// Simple dependencies without parentheses are accepted as well.
+    pub fn parse(dependency: &str) -> Result<RichDep, Error> {
+        let trimmed = dependency.trim();
+
+        if !trimmed.starts_with('(') {
+            return Dependency::parse(trimmed).map(RichDep::Single);
+        }
+
+        let cstr =
+            CString::new(trimmed).map_err(|e| format_err!(ErrorKind::InvalidInput, "{}", e))?;
+        let mut builder = Builder::default();
+        let mut cursor = cstr.as_ptr();
+        let mut emsg: *mut c_char = ptr::null_mut();
+        let cbdata: *mut Builder = &mut builder;
+
+        let rc = unsafe {
+            librpm_sys::rpmrichParse(
+                &mut cursor,
+                &mut emsg,
+                Some(parse_callback),
+                cbdata as *mut c_void,
+            )
+        };
+
+        let message = if emsg.is_null() {
+            None
+        } else {
+            let message = unsafe { CStr::from_ptr(emsg) }
+                .to_string_lossy()
+                .into_owned();
+            unsafe { librpm_sys::rfree(emsg as *mut c_void) };
+            Some(message)
+        };
+
+        if rc != librpm_sys::rpmRC_e_RPMRC_OK {
+            let reason = builder
+                .error
+                .or(message)
+                .unwrap_or_else(|| "syntax error".to_owned());
+
+            fail!(
+                ErrorKind::InvalidInput,
+                "invalid rich dependency: {} ({})",
+                dependency,
+                reason
+            );
+        }
+
+        let rest = unsafe { CStr::from_ptr(cursor) }.to_string_lossy();
+
+        if !rest.trim().is_empty() {
+            fail!(
+                ErrorKind::InvalidInput,
+                "trailing characters after rich dependency: {}",
+                dependency
+            );
+        }
+
+        builder.finish().ok_or_else(|| {
+            format_err!(
+                ErrorKind::InvalidInput,
+                "invalid rich dependency: {}",
+                dependency
+            )
+        })
+    }
+
+    /// Evaluate this dependency as a requirement, where `is_provided` tells
+    /// whether a simple dependency is satisfied.
+    ///
+    /// `with` and `without` need all their operands to be satisfied by the
+    /// same package, which can't be told from simple dependencies alone: here
+    /// they are evaluated like `and` and `and not`.
+    pub fn evaluate<F>(&self, is_provided: &mut F) -> bool
+    where
+        F: FnMut(&Dependency) -> bool,
+    {
+        match self {
+            RichDep::Single(dependency) => is_provided(dependency),
+            RichDep::And(operands) | RichDep::With(operands) => {
+                operands.iter().all(|o| o.evaluate(is_provided))
+            }
+            RichDep::Or(operands) => operands.iter().any(|o| o.evaluate(is_provided)),
+            RichDep::Without(required, excluded) => {
+                required.evaluate(is_provided) && !excluded.evaluate(is_provided)
+            }
+            RichDep::If {
+                then,
+                condition,
+                otherwise,
+            } => {
+                if condition.evaluate(is_provided) {
+                    then.evaluate(is_provided)
+                } else {
+                    match otherwise {
+                        Some(otherwise) => otherwise.evaluate(is_provided),
+                        None => true,
+                    }
+                }
+            }
+            RichDep::Unless {
+                then,
+                condition,
+                otherwise,
+            } => {
+                if condition.evaluate(is_provided) {
+                    match otherwise {
+                        Some(otherwise) => otherwise.evaluate(is_provided),
+                        None => true,
+                    }
+                } else {
+                    then.evaluate(is_provided)
+                }
+            }
+        }
+    }
+
+    /// Evaluate this dependency as a requirement against a set of provided
+    /// capabilities, comparing versions with librpm (see `evaluate`).
+    ///
+    /// Fails if any of the dependencies contains null bytes (see
+    /// `Dependency::overlaps`).
+    pub fn is_satisfied_by(&self, provides: &[Dependency]) -> Result<bool, Error> {
+        let provides = provides
+            .iter()
+            .map(|p| DependencySet::single(Tag::PROVIDENAME, p))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut error = None;
+
+        let satisfied = self.evaluate(&mut |dependency| match DependencySet::single(
+            Tag::REQUIRENAME,
+            dependency,
+        ) {
+            Ok(required) => provides.iter().any(|provided| provided.overlaps(&required)),
+            Err(e) => {
+                error.get_or_insert(e);
+                false
+            }
+        });
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(satisfied),
+        }
+    }
+
+    /// Write the operands of this dependency separated by `op`
+    fn write_operands(f: &mut fmt::Formatter<'_>, operands: &[RichDep], op: &str) -> fmt::Result {
+        f.write_str("(")?;
+
+        for (i, operand) in operands.iter().enumerate() {
+            if i > 0 {
+                write!(f, " {} ", op)?;
+            }
+
+            write!(f, "{}", operand)?;
+        }
+
+        f.write_str(")")
+    }
+}
+
+impl FromStr for RichDep {
+    type Err = Error;
+
+    fn from_str(dependency: &str) -> Result<RichDep, Error> {
+        RichDep::parse(dependency)
+    }
+}
+
+/// Canonical form of the dependency, as written by rpmbuild
+impl fmt::Display for RichDep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RichDep::Single(dependency) => write!(f, "{}", dependency),
+            RichDep::And(operands) => Self::write_operands(f, operands, "and"),
+            RichDep::Or(operands) => Self::write_operands(f, operands, "or"),
+            RichDep::With(operands) => Self::write_operands(f, operands, "with"),
+            RichDep::Without(required, excluded) => {
+                write!(f, "({} without {})", required, excluded)
+            }
+            RichDep::If {
+                then,
+                condition,
+                otherwise,
+            } => match otherwise {
+                Some(otherwise) => write!(f, "({} if {} else {})", then, condition, otherwise),
+                None => write!(f, "({} if {})", then, condition),
+            },
+            RichDep::Unless {
+                then,
+                condition,
+                otherwise,
+            } => match otherwise {
+                Some(otherwise) => write!(f, "({} unless {} else {})", then, condition, otherwise),
+                None => write!(f, "({} unless {})", then, condition),
+            },
+        }
+    }
+}
+
+/// Operands and operators of a parenthesized group being parsed
+#[derive(Default)]
+struct Group {
+    operands: Vec<RichDep>,
+    ops: Vec<librpm_sys::rpmrichOp>,
+}
+
+impl Group {
+    /// Build the dependency for this group, if its operators make sense
+    fn build(self) -> Option<RichDep> {
+        let Group { mut operands, ops } = self;
+
+        if ops.is_empty() {
+            return if operands.len() == 1 {
+                operands.pop()
+            } else {
+                None
+            };
+        }
+
+        if operands.len() != ops.len() + 1 {
+            return None;
+        }
+
+        let first = ops[0];
+        let same = ops.iter().all(|&op| op == first);
+        let mut operands = operands.into_iter().map(Box::new);
+
+        match (first, ops.len()) {
+            (librpm_sys::rpmrichOp_e_RPMRICHOP_AND, _) if same => {
+                Some(RichDep::And(operands.map(|o| *o).collect()))
+            }
+            (librpm_sys::rpmrichOp_e_RPMRICHOP_OR, _) if same => {
+                Some(RichDep::Or(operands.map(|o| *o).collect()))
+            }
+            (librpm_sys::rpmrichOp_e_RPMRICHOP_WITH, _) if same => {
+                Some(RichDep::With(operands.map(|o| *o).collect()))
+            }
+            (librpm_sys::rpmrichOp_e_RPMRICHOP_WITHOUT, 1) => {
+                Some(RichDep::Without(operands.next()?, operands.next()?))
+            }
+            (librpm_sys::rpmrichOp_e_RPMRICHOP_IF, _)
+            | (librpm_sys::rpmrichOp_e_RPMRICHOP_UNLESS, _) => {
+                let otherwise = match ops.get(1) {
+                    None => false,
+                    Some(&librpm_sys::rpmrichOp_e_RPMRICHOP_ELSE) if ops.len() == 2 => true,
+                    Some(_) => return None,
+                };
+
+                let then = operands.next()?;
+                let condition = operands.next()?;
+                let otherwise = if otherwise { operands.next() } else { None };
+
+                if first == librpm_sys::rpmrichOp_e_RPMRICHOP_IF {
+                    Some(RichDep::If {
+                        then,
+                        condition,
+                        otherwise,
+                    })
+                } else {
+                    Some(RichDep::Unless {
+                        then,
+                        condition,
+                        otherwise,
+                    })
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// State of `rpmrichParse`'s callbacks
+#[derive(Default)]
+struct Builder {
+    /// Groups being parsed, innermost last
+    groups: Vec<Group>,
+
+    /// Dependency parsed so far (the outermost group)
+    result: Option<RichDep>,
+
+    /// Error found while building the dependency
+    error: Option<String>,
+}
+
+impl Builder {
+    /// Handle a parser event, returning false on error
+    fn handle(
+        &mut self,
+        kind: librpm_sys::rpmrichParseType,
+        dependency: Option<Dependency>,
+        op: librpm_sys::rpmrichOp,
+    ) -> bool {
+        match kind {
+            librpm_sys::rpmrichParseType_e_RPMRICH_PARSE_ENTER => {
+                self.groups.push(Group::default());
+            }
+            librpm_sys::rpmrichParseType_e_RPMRICH_PARSE_SIMPLE => {
+                match (self.groups.last_mut(), dependency) {
+                    (Some(group), Some(dependency)) => {
+                        group.operands.push(RichDep::Single(dependency))
+                    }
+                    _ => return false,
+                }
+            }
+            librpm_sys::rpmrichParseType_e_RPMRICH_PARSE_OP => match self.groups.last_mut() {
+                Some(group) => group.ops.push(op),
+                None => return false,
+            },
+            librpm_sys::rpmrichParseType_e_RPMRICH_PARSE_LEAVE => {
+                let built = match self.groups.pop().and_then(Group::build) {
+                    Some(built) => built,
+                    None => return false,
+                };
+
+                match self.groups.last_mut() {
+                    Some(parent) => parent.operands.push(built),
+                    None => self.result = Some(built),
+                }
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// The parsed dependency, if parsing completed
+    fn finish(self) -> Option<RichDep> {
+        if self.groups.is_empty() {
+            self.result
+        } else {
+            None
+        }
+    }
+}
+
+/// Callback invoked by `rpmrichParse` for every part of the dependency
+unsafe extern "C" fn parse_callback(
+    cbdata: *mut c_void,
+    kind: librpm_sys::rpmrichParseType,
+    n: *const c_char,
+    nl: c_int,
+    e: *const c_char,
+    el: c_int,
+    sense: librpm_sys::rpmsenseFlags,
+    op: librpm_sys::rpmrichOp,
+    _emsg: *mut *mut c_char,
+) -> librpm_sys::rpmRC {
+    let builder = &mut *(cbdata as *mut Builder);
+
+    let dependency = if kind == librpm_sys::rpmrichParseType_e_RPMRICH_PARSE_SIMPLE {
+        match (substring(n, nl), substring(e, el)) {
+            // The parsed string can't contain null bytes
+            (Some(name), version) => {
+                Dependency::new(name, DependencyFlags::from_bits(sense), version).ok()
+            }
+            (None, _) => None,
+        }
+    } else {
+        None
+    };
+
+    if builder.handle(kind, dependency, op) {
+        librpm_sys::rpmRC_e_RPMRC_OK
+    } else {
+        builder.error = Some("unsupported combination of operators".to_owned());
+        librpm_sys::rpmRC_e_RPMRC_FAIL
+    }
+}
+
+/// Copy a string given as pointer and length by librpm
+unsafe fn substring(ptr: *const c_char, len: c_int) -> Option<String> {
+    if ptr.is_null() || len <= 0 {
+        return None;
+    }
+
+    let bytes = slice::from_raw_parts(ptr as *const u8, len as usize);
+    str::from_utf8(bytes).ok().map(str::to_owned)
+}
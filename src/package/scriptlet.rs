@@ -0,0 +1,221 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Package scriptlets: programs run when a package is installed or removed
+
+use crate::internal::{header::Header, tag::Tag, td::TagData};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Interpreter librpm runs scriptlets with when none is given
+const DEFAULT_INTERPRETER: &str = "/bin/sh";
+
+/// Kinds of scriptlets, i.e. when they are run
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScriptletKind {
+    /// Run before the package is installed (`%pre`)
+    PreIn,
+
+    /// Run after the package is installed (`%post`)
+    PostIn,
+
+    /// Run before the package is removed (`%preun`)
+    PreUn,
+
+    /// Run after the package is removed (`%postun`)
+    PostUn,
+
+    /// Run before the transaction installing the package (`%pretrans`)
+    PreTrans,
+
+    /// Run after the transaction installing the package (`%posttrans`)
+    PostTrans,
+
+    /// Run when the package is verified (`%verifyscript`)
+    Verify,
+}
+
+impl ScriptletKind {
+    /// All kinds of scriptlets: those run by an install or removal in the
+    /// order librpm runs them, then `Verify`, which only `rpm --verify` runs
+    pub const ALL: [ScriptletKind; 7] = [
+        ScriptletKind::PreTrans,
+        ScriptletKind::PreIn,
+        ScriptletKind::PostIn,
+        ScriptletKind::PreUn,
+        ScriptletKind::PostUn,
+        ScriptletKind::PostTrans,
+        ScriptletKind::Verify,
+    ];
+
+    /// Name of the spec file section for this kind of scriptlet
+    pub fn section(self) -> &'static str {
+        match self {
+            ScriptletKind::PreIn => "%pre",
+            ScriptletKind::PostIn => "%post",
+            ScriptletKind::PreUn => "%preun",
+            ScriptletKind::PostUn => "%postun",
+            ScriptletKind::PreTrans => "%pretrans",
+            ScriptletKind::PostTrans => "%posttrans",
+            ScriptletKind::Verify => "%verifyscript",
+        }
+    }
+
+    /// Header tags holding the body, interpreter and flags of the scriptlet
+    fn tags(self) -> (Tag, Tag, Tag) {
+        match self {
+            ScriptletKind::PreIn => (Tag::PREIN, Tag::PREINPROG, Tag::PREINFLAGS),
+            ScriptletKind::PostIn => (Tag::POSTIN, Tag::POSTINPROG, Tag::POSTINFLAGS),
+            ScriptletKind::PreUn => (Tag::PREUN, Tag::PREUNPROG, Tag::PREUNFLAGS),
+            ScriptletKind::PostUn => (Tag::POSTUN, Tag::POSTUNPROG, Tag::POSTUNFLAGS),
+            ScriptletKind::PreTrans => (Tag::PRETRANS, Tag::PRETRANSPROG, Tag::PRETRANSFLAGS),
+            ScriptletKind::PostTrans => (Tag::POSTTRANS, Tag::POSTTRANSPROG, Tag::POSTTRANSFLAGS),
+            ScriptletKind::Verify => (
+                Tag::VERIFYSCRIPT,
+                Tag::VERIFYSCRIPTPROG,
+                Tag::VERIFYSCRIPTFLAGS,
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ScriptletKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.section())
+    }
+}
+
+/// Flags controlling how scriptlets are run (`rpmscriptFlags` in librpm)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScriptletFlags(u32);
+
+impl ScriptletFlags {
+    /// Macros in the body are expanded before running it (`-e`)
+    pub const EXPAND: ScriptletFlags = ScriptletFlags(1 << 0);
+
+    /// Header query formats in the body are expanded before running it (`-q`)
+    pub const QFORMAT: ScriptletFlags = ScriptletFlags(1 << 1);
+
+    /// Failure of the scriptlet aborts the transaction
+    pub const CRITICAL: ScriptletFlags = ScriptletFlags(1 << 2);
+
+    /// Create flags from their raw value in the header
+    pub fn from_bits(bits: u32) -> Self {
+        ScriptletFlags(bits)
+    }
+
+    /// Raw value of the flags
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Are all of the given flags set?
+    pub fn contains(self, other: ScriptletFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+/// Scriptlet contained in a package
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Scriptlet {
+    pub(crate) kind: ScriptletKind,
+    pub(crate) interpreter: String,
+    pub(crate) args: Vec<String>,
+    pub(crate) body: Option<String>,
+    pub(crate) flags: ScriptletFlags,
+}
+
+impl Scriptlet {
+    /// When this scriptlet is run
+    pub fn kind(&self) -> ScriptletKind {
+        self.kind
+    }
+
+    /// Program which runs the scriptlet (`/bin/sh` unless specified)
+    pub fn interpreter(&self) -> &str {
+        &self.interpreter
+    }
+
+    /// Arguments passed to the interpreter
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Script passed to the interpreter, if any (e.g. `%post -p
+    /// /sbin/ldconfig` has none)
+    pub fn body(&self) -> Option<&str> {
+        self.body.as_deref()
+    }
+
+    /// Flags controlling how the scriptlet is run
+    pub fn flags(&self) -> ScriptletFlags {
+        self.flags
+    }
+
+    /// Read all scriptlets from a header
+    pub(crate) fn from_header(header: &Header) -> Vec<Self> {
+        ScriptletKind::ALL
+            .iter()
+            .filter_map(|&kind| Self::read(header, kind))
+            .collect()
+    }
+
+    /// Read a single kind of scriptlet from a header, if present
+    fn read(header: &Header, kind: ScriptletKind) -> Option<Self> {
+        let (body_tag, prog_tag, flags_tag) = kind.tags();
+
+        let body = header
+            .get(body_tag)
+            .and_then(|d| d.as_str().map(ToOwned::to_owned));
+
+        // Older packages store the interpreter as a plain string, newer ones
+        // as an array of the interpreter followed by its arguments
+        let mut prog: Vec<String> = match header.get(prog_tag) {
+            Some(TagData::StrArray(args)) => args,
+            Some(data) => data.as_str().map(ToOwned::to_owned).into_iter().collect(),
+            None => vec![],
+        };
+
+        if body.is_none() && prog.is_empty() {
+            return None;
+        }
+
+        let interpreter = if prog.is_empty() {
+            DEFAULT_INTERPRETER.to_owned()
+        } else {
+            prog.remove(0)
+        };
+
+        let flags = header
+            .get(flags_tag)
+            .and_then(|d| d.to_int32())
+            .map(|bits| ScriptletFlags::from_bits(bits as u32))
+            .unwrap_or_default();
+
+        Some(Scriptlet {
+            kind,
+            interpreter,
+            args: prog,
+            body,
+            flags,
+        })
+    }
+}
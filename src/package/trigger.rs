@@ -0,0 +1,310 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Triggers: scripts run when other packages (or files) are installed or
+//! removed
+//!
+//! librpm stores triggers as parallel arrays of conditions, each of which
+//! refers to a script by its index in a second set of parallel arrays.
+
+use super::{
+    dependency::{Dependency, DependencyFlags},
+    scriptlet::ScriptletFlags,
+};
+use crate::internal::{header::Header, tag::Tag, td::TagData};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Interpreter librpm runs triggers with when none is given
+const DEFAULT_INTERPRETER: &str = "/bin/sh";
+
+/// Kinds of triggers, i.e. when they are run
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TriggerKind {
+    /// Run before the condition is installed (`%triggerprein`)
+    PreIn,
+
+    /// Run after the condition is installed (`%triggerin`)
+    In,
+
+    /// Run before the condition is removed (`%triggerun`)
+    Un,
+
+    /// Run after the condition is removed (`%triggerpostun`)
+    PostUn,
+}
+
+impl TriggerKind {
+    /// Determine the kind of trigger from the flags of its condition
+    fn from_flags(flags: DependencyFlags) -> Option<Self> {
+        if flags.contains(DependencyFlags::TRIGGERPREIN) {
+            Some(TriggerKind::PreIn)
+        } else if flags.contains(DependencyFlags::TRIGGERIN) {
+            Some(TriggerKind::In)
+        } else if flags.contains(DependencyFlags::TRIGGERUN) {
+            Some(TriggerKind::Un)
+        } else if flags.contains(DependencyFlags::TRIGGERPOSTUN) {
+            Some(TriggerKind::PostUn)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for TriggerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TriggerKind::PreIn => "prein",
+            TriggerKind::In => "in",
+            TriggerKind::Un => "un",
+            TriggerKind::PostUn => "postun",
+        })
+    }
+}
+
+/// Trigger on another package
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Trigger {
+    pub(crate) kind: TriggerKind,
+    pub(crate) condition: Dependency,
+    pub(crate) interpreter: String,
+    pub(crate) script: Option<String>,
+    pub(crate) flags: ScriptletFlags,
+}
+
+impl Trigger {
+    /// When this trigger is run
+    pub fn kind(&self) -> TriggerKind {
+        self.kind
+    }
+
+    /// Package (and optionally version range) which sets off the trigger
+    pub fn condition(&self) -> &Dependency {
+        &self.condition
+    }
+
+    /// Program which runs the trigger script (`/bin/sh` unless specified)
+    pub fn interpreter(&self) -> &str {
+        &self.interpreter
+    }
+
+    /// Script passed to the interpreter, if any
+    pub fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    /// Flags controlling how the script is run
+    pub fn flags(&self) -> ScriptletFlags {
+        self.flags
+    }
+
+    /// Read all package triggers from a header
+    pub(crate) fn from_header(header: &Header) -> Vec<Self> {
+        RawTrigger::read(header, &TRIGGER_TAGS)
+            .into_iter()
+            .filter_map(|raw| {
+                Some(Trigger {
+                    kind: TriggerKind::from_flags(raw.flags)?,
+                    condition: Dependency::new(raw.name, raw.flags, raw.version).ok()?,
+                    interpreter: raw.interpreter,
+                    script: raw.script,
+                    flags: raw.script_flags,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Trigger on files installed or removed by any package
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FileTrigger {
+    pub(crate) kind: TriggerKind,
+    pub(crate) prefix: String,
+    pub(crate) interpreter: String,
+    pub(crate) script: Option<String>,
+    pub(crate) flags: ScriptletFlags,
+    pub(crate) priority: i32,
+}
+
+impl FileTrigger {
+    /// When this trigger is run
+    pub fn kind(&self) -> TriggerKind {
+        self.kind
+    }
+
+    /// Path prefix of the files which set off the trigger
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Program which runs the trigger script (`/bin/sh` unless specified)
+    pub fn interpreter(&self) -> &str {
+        &self.interpreter
+    }
+
+    /// Script passed to the interpreter, if any
+    pub fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    /// Flags controlling how the script is run
+    pub fn flags(&self) -> ScriptletFlags {
+        self.flags
+    }
+
+    /// Priority of the trigger: higher priorities run first
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// Read all file triggers from a header
+    pub(crate) fn from_header(header: &Header) -> Vec<Self> {
+        Self::read(header, &FILE_TRIGGER_TAGS)
+    }
+
+    /// Read all transaction file triggers from a header
+    pub(crate) fn transaction_from_header(header: &Header) -> Vec<Self> {
+        Self::read(header, &TRANS_FILE_TRIGGER_TAGS)
+    }
+
+    fn read(header: &Header, tags: &TriggerTags) -> Vec<Self> {
+        RawTrigger::read(header, tags)
+            .into_iter()
+            .filter_map(|raw| {
+                Some(FileTrigger {
+                    kind: TriggerKind::from_flags(raw.flags)?,
+                    prefix: raw.name,
+                    interpreter: raw.interpreter,
+                    script: raw.script,
+                    flags: raw.script_flags,
+                    priority: raw.priority,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Header tags describing one kind of trigger
+struct TriggerTags {
+    name: Tag,
+    version: Tag,
+    flags: Tag,
+    index: Tag,
+    scripts: Tag,
+    script_prog: Tag,
+    script_flags: Tag,
+    priorities: Option<Tag>,
+}
+
+const TRIGGER_TAGS: TriggerTags = TriggerTags {
+    name: Tag::TRIGGERNAME,
+    version: Tag::TRIGGERVERSION,
+    flags: Tag::TRIGGERFLAGS,
+    index: Tag::TRIGGERINDEX,
+    scripts: Tag::TRIGGERSCRIPTS,
+    script_prog: Tag::TRIGGERSCRIPTPROG,
+    script_flags: Tag::TRIGGERSCRIPTFLAGS,
+    priorities: None,
+};
+
+const FILE_TRIGGER_TAGS: TriggerTags = TriggerTags {
+    name: Tag::FILETRIGGERNAME,
+    version: Tag::FILETRIGGERVERSION,
+    flags: Tag::FILETRIGGERFLAGS,
+    index: Tag::FILETRIGGERINDEX,
+    scripts: Tag::FILETRIGGERSCRIPTS,
+    script_prog: Tag::FILETRIGGERSCRIPTPROG,
+    script_flags: Tag::FILETRIGGERSCRIPTFLAGS,
+    priorities: Some(Tag::FILETRIGGERPRIORITIES),
+};
+
+const TRANS_FILE_TRIGGER_TAGS: TriggerTags = TriggerTags {
+    name: Tag::TRANSFILETRIGGERNAME,
+    version: Tag::TRANSFILETRIGGERVERSION,
+    flags: Tag::TRANSFILETRIGGERFLAGS,
+    index: Tag::TRANSFILETRIGGERINDEX,
+    scripts: Tag::TRANSFILETRIGGERSCRIPTS,
+    script_prog: Tag::TRANSFILETRIGGERSCRIPTPROG,
+    script_flags: Tag::TRANSFILETRIGGERSCRIPTFLAGS,
+    priorities: Some(Tag::TRANSFILETRIGGERPRIORITIES),
+};
+
+/// Trigger condition joined with the script it refers to
+struct RawTrigger {
+    name: String,
+    flags: DependencyFlags,
+    version: Option<String>,
+    interpreter: String,
+    script: Option<String>,
+    script_flags: ScriptletFlags,
+    priority: i32,
+}
+
+impl RawTrigger {
+    fn read(header: &Header, tags: &TriggerTags) -> Vec<Self> {
+        let strings = |tag: Tag| match header.get(tag) {
+            Some(TagData::StrArray(values)) => values,
+            _ => vec![],
+        };
+
+        let int32s = |tag: Option<Tag>| match tag.and_then(|t| header.get(t)) {
+            Some(TagData::Int32Array(values)) => values,
+            _ => vec![],
+        };
+
+        let names = strings(tags.name);
+
+        if names.is_empty() {
+            return vec![];
+        }
+
+        let versions = strings(tags.version);
+        let scripts = strings(tags.scripts);
+        let progs = strings(tags.script_prog);
+        let flags = int32s(Some(tags.flags));
+        let indexes = int32s(Some(tags.index));
+        let script_flags = int32s(Some(tags.script_flags));
+        let priorities = int32s(tags.priorities);
+
+        names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let ix = indexes.get(i).map(|&ix| ix as usize).unwrap_or(i);
+
+                RawTrigger {
+                    name,
+                    flags: DependencyFlags::from_bits(flags.get(i).copied().unwrap_or(0) as u32),
+                    version: versions.get(i).cloned(),
+                    interpreter: progs
+                        .get(ix)
+                        .cloned()
+                        .unwrap_or_else(|| DEFAULT_INTERPRETER.to_owned()),
+                    script: scripts.get(ix).filter(|s| !s.is_empty()).cloned(),
+                    script_flags: ScriptletFlags::from_bits(
+                        script_flags.get(ix).copied().unwrap_or(0) as u32,
+                    ),
+                    priority: priorities.get(ix).copied().unwrap_or(0),
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,412 @@
+#!/usr/bin/env python3
+"""Generate the synthetic package files in testdata/rpms
+
+Writes binary packages in the format of rpmbuild (rpm >= 4.14): a lead, a
+signature header with sizes, digests and optionally an OpenPGP signature of
+the main header, the main header, and a cpio payload compressed with gzip,
+xz or zstd. Run from the testdata directory; needs gpg and zstd.
+
+The signing keys are kept in testdata/keys, so the key IDs stay the same
+when the packages are regenerated. The signature packets are also written
+to testdata/signatures, with variants in the other OpenPGP packet format
+and without the issuer key ID subpacket.
+"""
+
+import glob
+import gzip
+import hashlib
+import lzma
+import os
+import struct
+import subprocess
+import tempfile
+
+# Tag numbers and types (from rpmtag.h)
+HEADERSIGNATURES, HEADERIMMUTABLE = 62, 63
+HEADERI18NTABLE = 100
+NAME, VERSION, RELEASE = 1000, 1001, 1002
+SUMMARY, DESCRIPTION, BUILDTIME, BUILDHOST = 1004, 1005, 1006, 1007
+SIZE, LICENSE, GROUP, OS, ARCH = 1009, 1014, 1016, 1021, 1022
+FILESIZES, FILEMODES, FILERDEVS, FILEMTIMES = 1028, 1030, 1033, 1034
+FILEDIGESTS, FILELINKTOS, FILEFLAGS = 1035, 1036, 1037
+FILEUSERNAME, FILEGROUPNAME, SOURCERPM, FILEVERIFYFLAGS = 1039, 1040, 1044, 1045
+PROVIDENAME, REQUIREFLAGS, REQUIRENAME, REQUIREVERSION = 1047, 1048, 1049, 1050
+RPMVERSION, FILEDEVICES, FILEINODES, FILELANGS = 1064, 1095, 1096, 1097
+PROVIDEFLAGS, PROVIDEVERSION = 1112, 1113
+DIRINDEXES, BASENAMES, DIRNAMES = 1116, 1117, 1118
+PAYLOADFORMAT, PAYLOADCOMPRESSOR, PAYLOADFLAGS = 1124, 1125, 1126
+FILEDIGESTALGO, PAYLOADDIGEST, PAYLOADDIGESTALGO = 5011, 5092, 5093
+
+# Signature tags (from rpmtag.h)
+SIG_RSA, SIG_SHA1, SIG_SHA256 = 268, 269, 273
+SIG_SIZE, SIG_MD5, SIG_PAYLOADSIZE = 1000, 1004, 1007
+
+CHAR, INT16, INT32, STRING, BIN, STRING_ARRAY, I18NSTRING = 1, 3, 4, 6, 7, 8, 9
+
+ALIGNMENT = {INT16: 2, INT32: 4}
+
+# Dependency and file flags (from rpmds.h and rpmfiles.h)
+RPMSENSE_LESS, RPMSENSE_EQUAL, RPMSENSE_RPMLIB = 2, 8, 1 << 24
+RPMFILE_DOC = 1 << 1
+
+# SHA256, as in FILEDIGESTALGO and PAYLOADDIGESTALGO
+PGPHASHALGO_SHA256 = 8
+
+HEADER_MAGIC = b"\x8e\xad\xe8\x01\0\0\0\0"
+LEAD_MAGIC = b"\xed\xab\xee\xdb"
+
+# Modification time of every file, and build time of every package
+MTIME = 1600000000
+
+# Keys the signed packages are signed with, by file name in testdata/keys
+KEYS = {
+    "rsa": ("RSA Test Key <rsa@example.com>", "rsa2048"),
+    "eddsa": ("EdDSA Test Key <eddsa@example.com>", "ed25519"),
+}
+
+# rpmlib() requirements of every package, and those of each compressor
+RPMLIB = [
+    ("rpmlib(CompressedFileNames)", "3.0.4-1"),
+    ("rpmlib(FileDigests)", "4.6.0-1"),
+    ("rpmlib(PayloadFilesHavePrefix)", "4.0-1"),
+]
+COMPRESSOR_RPMLIB = {
+    "gzip": [],
+    "xz": [("rpmlib(PayloadIsXz)", "5.2-1")],
+    "zstd": [("rpmlib(PayloadIsZstd)", "5.4.18-1")],
+}
+
+# Files of the hello packages, sorted by path as rpmbuild does:
+# path, mode, contents (or symlink target), inode
+HELLO_FILES = [
+    ("/usr/bin/hello", 0o100755, b"#!/bin/sh\necho 'Hello, world!'\n", 1),
+    ("/usr/bin/hi", 0o120777, b"hello", 2),
+    ("/usr/share/doc/hello/README", 0o100644, b"Says hello.\n", 3),
+    ("/usr/share/hello", 0o40755, b"", 4),
+    ("/usr/share/hello/greeting", 0o100644, b"Hello, world!\n", 5),
+    ("/usr/share/hello/greeting.en", 0o100644, b"Hello, world!\n", 5),
+]
+
+# Packages which try to write outside of the directory they're extracted to
+EVIL_FILES = {
+    "evil-parent": [
+        ("/../evil", 0o100644, b"outside\n", 1),
+        ("/usr/share/evil", 0o100644, b"inside\n", 2),
+    ],
+    "evil-symlink": [
+        ("/escape", 0o120777, b"../outside", 1),
+        ("/escape/evil", 0o100644, b"outside\n", 2),
+    ],
+}
+
+# A package whose header lists the digest of other contents than those in
+# its payload, as if the payload had been tampered with
+TAMPERED_FILES = [("/usr/share/tampered", 0o100644, b"tampered\n", 1)]
+ORIGINAL_CONTENTS = {"/usr/share/tampered": b"original\n"}
+
+# Packages declaring another compressor than the one their payload is
+# compressed with, by name
+DECLARED_COMPRESSOR = {"bad-compressor": "ufdio"}
+
+# name, compressor, signing key, files
+PACKAGES = [
+    ("hello", "gzip", "rsa", HELLO_FILES),
+    ("hello-xz", "xz", "eddsa", HELLO_FILES),
+    ("hello-zstd", "zstd", None, HELLO_FILES),
+    ("evil-parent", "gzip", None, EVIL_FILES["evil-parent"]),
+    ("evil-symlink", "gzip", None, EVIL_FILES["evil-symlink"]),
+    ("tampered", "gzip", None, TAMPERED_FILES),
+    ("bad-compressor", "gzip", None, HELLO_FILES),
+]
+
+
+def encode(kind, value):
+    if kind == CHAR:
+        return bytes(value), len(value)
+    if kind == INT16:
+        return b"".join(struct.pack(">H", v & 0xffff) for v in value), len(value)
+    if kind == INT32:
+        return b"".join(struct.pack(">I", v & 0xffffffff) for v in value), len(value)
+    if kind == STRING:
+        return value.encode() + b"\0", 1
+    if kind in (STRING_ARRAY, I18NSTRING):
+        return b"".join(v.encode() + b"\0" for v in value), len(value)
+    if kind == BIN:
+        return value, len(value)
+    raise ValueError(kind)
+
+
+def header(tags, region_tag):
+    """Build a header (with magic) holding `tags` in a single region, as in
+    package files"""
+    entries = []
+    data = b""
+
+    for tag, kind, value in sorted(tags):
+        data += b"\0" * (-len(data) % ALIGNMENT.get(kind, 1))
+        encoded, count = encode(kind, value)
+        entries.append(struct.pack(">iiii", tag, kind, len(data), count))
+        data += encoded
+
+    entries.insert(0, struct.pack(">iiii", region_tag, BIN, len(data), 16))
+    data += struct.pack(">iiii", region_tag, BIN, -len(entries) * 16, 16)
+    return HEADER_MAGIC + struct.pack(">ii", len(entries), len(data)) + b"".join(entries) + data
+
+
+def lead(nevr):
+    """The lead of a binary package: version 3.0, Linux, header signatures"""
+    return struct.pack(">4sBBhh66shh16s", LEAD_MAGIC, 3, 0, 0, 0, nevr.encode(),
+                       1, 5, b"")
+
+
+def cpio_entry(name, mode, nlink, inode, contents):
+    """An entry of a cpio archive in the "new ASCII" format"""
+    name = name.encode() + b"\0"
+    fields = [inode, mode, 0, 0, nlink, MTIME, len(contents), 0, 0, 0, 0,
+              len(name), 0]
+    entry = b"070701" + b"".join(b"%08x" % f for f in fields) + name
+    entry += b"\0" * (-len(entry) % 4) + contents
+    return entry + b"\0" * (-len(entry) % 4)
+
+
+def payload(files):
+    """The uncompressed cpio payload. The contents of hardlinks are stored
+    with the last of them."""
+    nlinks = {}
+    for _, _, _, inode in files:
+        nlinks[inode] = nlinks.get(inode, 0) + 1
+
+    seen = {}
+    archive = b""
+
+    for path, mode, contents, inode in files:
+        seen[inode] = seen.get(inode, 0) + 1
+        if seen[inode] < nlinks[inode]:
+            contents = b""
+        archive += cpio_entry("." + path, mode, nlinks[inode], inode, contents)
+
+    return archive + cpio_entry("TRAILER!!!", 0, 1, 0, b"")
+
+
+def compress(data, compressor):
+    if compressor == "gzip":
+        return gzip.compress(data, 9, mtime=0)
+    if compressor == "xz":
+        return lzma.compress(data, preset=2)
+    if compressor == "zstd":
+        return subprocess.run(["zstd", "-q", "-19", "-c"], input=data,
+                              stdout=subprocess.PIPE, check=True).stdout
+    raise ValueError(compressor)
+
+
+def file_tags(files):
+    """The file list tags of the main header"""
+    dirnames = []
+    dirindexes = []
+    basenames = []
+
+    for path, _, _, _ in files:
+        dirname, basename = path.rsplit("/", 1)
+        if dirname + "/" not in dirnames:
+            dirnames.append(dirname + "/")
+        dirindexes.append(dirnames.index(dirname + "/"))
+        basenames.append(basename)
+
+    def is_file(mode):
+        return mode & 0o170000 == 0o100000
+
+    def is_symlink(mode):
+        return mode & 0o170000 == 0o120000
+
+    return [
+        (FILESIZES, INT32, [len(c) if is_file(m) or is_symlink(m) else 4096
+                            for _, m, c, _ in files]),
+        (FILEMODES, INT16, [m for _, m, _, _ in files]),
+        (FILERDEVS, INT16, [0 for _ in files]),
+        (FILEMTIMES, INT32, [MTIME for _ in files]),
+        (FILEDIGESTS, STRING_ARRAY, [hashlib.sha256(ORIGINAL_CONTENTS.get(p, c)).hexdigest()
+                                     if is_file(m) else "" for p, m, c, _ in files]),
+        (FILELINKTOS, STRING_ARRAY, [c.decode() if is_symlink(m) else ""
+                                     for _, m, c, _ in files]),
+        (FILEFLAGS, INT32, [RPMFILE_DOC if "/doc/" in p else 0 for p, _, _, _ in files]),
+        (FILEUSERNAME, STRING_ARRAY, ["root" for _ in files]),
+        (FILEGROUPNAME, STRING_ARRAY, ["root" for _ in files]),
+        (FILEVERIFYFLAGS, INT32, [-1 for _ in files]),
+        (FILEDEVICES, INT32, [1 for _ in files]),
+        (FILEINODES, INT32, [i for _, _, _, i in files]),
+        (FILELANGS, STRING_ARRAY, ["" for _ in files]),
+        (DIRINDEXES, INT32, dirindexes),
+        (BASENAMES, STRING_ARRAY, basenames),
+        (DIRNAMES, STRING_ARRAY, dirnames),
+        (FILEDIGESTALGO, INT32, [PGPHASHALGO_SHA256]),
+    ]
+
+
+def main_header(name, compressor, files, compressed):
+    requires = RPMLIB + COMPRESSOR_RPMLIB[compressor]
+    tags = [
+        (HEADERI18NTABLE, STRING_ARRAY, ["C"]),
+        (NAME, STRING, name),
+        (VERSION, STRING, "1.0"),
+        (RELEASE, STRING, "1"),
+        (SUMMARY, I18NSTRING, ["Says hello"]),
+        (DESCRIPTION, I18NSTRING, ["A test package, compressed with %s." % compressor]),
+        (BUILDTIME, INT32, [MTIME]),
+        (BUILDHOST, STRING, "localhost"),
+        (SIZE, INT32, [sum(len(c) for _, m, c, _ in files if m & 0o170000 == 0o100000)]),
+        (LICENSE, STRING, "MIT"),
+        (GROUP, I18NSTRING, ["Unspecified"]),
+        (OS, STRING, "linux"),
+        (ARCH, STRING, "noarch"),
+        (SOURCERPM, STRING, "%s-1.0-1.src.rpm" % name),
+        (PROVIDENAME, STRING_ARRAY, [name]),
+        (PROVIDEFLAGS, INT32, [RPMSENSE_EQUAL]),
+        (PROVIDEVERSION, STRING_ARRAY, ["1.0-1"]),
+        (REQUIRENAME, STRING_ARRAY, [n for n, _ in requires]),
+        (REQUIREFLAGS, INT32, [RPMSENSE_RPMLIB | RPMSENSE_LESS | RPMSENSE_EQUAL
+                               for _ in requires]),
+        (REQUIREVERSION, STRING_ARRAY, [v for _, v in requires]),
+        (RPMVERSION, STRING, "4.16.1.3"),
+        (PAYLOADFORMAT, STRING, "cpio"),
+        (PAYLOADCOMPRESSOR, STRING, DECLARED_COMPRESSOR.get(name, compressor)),
+        (PAYLOADFLAGS, STRING, "9" if compressor == "gzip" else "2"),
+        (PAYLOADDIGEST, STRING_ARRAY, [hashlib.sha256(compressed).hexdigest()]),
+        (PAYLOADDIGESTALGO, INT32, [PGPHASHALGO_SHA256]),
+    ]
+    return header(tags + file_tags(files), HEADERIMMUTABLE)
+
+
+def signature_header(main, compressed, archive_size, packet):
+    tags = [
+        (SIG_SHA1, STRING, hashlib.sha1(main).hexdigest()),
+        (SIG_SHA256, STRING, hashlib.sha256(main).hexdigest()),
+        (SIG_SIZE, INT32, [len(main) + len(compressed)]),
+        (SIG_MD5, BIN, hashlib.md5(main + compressed).digest()),
+        (SIG_PAYLOADSIZE, INT32, [archive_size]),
+    ]
+    if packet is not None:
+        tags.append((SIG_RSA, BIN, packet))
+
+    sigh = header(tags, HEADERSIGNATURES)
+    # The signature header is padded to a multiple of 8 bytes
+    return sigh + b"\0" * (-len(sigh) % 8)
+
+
+def gpg(home, *args, **kwargs):
+    return subprocess.run(["gpg", "--homedir", home, "--batch", "--quiet",
+                           "--pinentry-mode", "loopback", "--passphrase", ""]
+                          + list(args), stdout=subprocess.PIPE, check=True,
+                          **kwargs).stdout
+
+
+def load_keys(home):
+    """Import the signing keys, creating them on first use. Returns the
+    fingerprints by key name."""
+    os.makedirs("keys", exist_ok=True)
+    fingerprints = {}
+
+    for key, (uid, algorithm) in KEYS.items():
+        secret = "keys/%s.secret.asc" % key
+
+        if not os.path.exists(secret):
+            gpg(home, "--quick-gen-key", uid, algorithm, "sign", "never")
+            with open(secret, "wb") as f:
+                f.write(gpg(home, "--armor", "--export-secret-keys", uid))
+            with open("keys/RPM-GPG-KEY-%s" % key, "wb") as f:
+                f.write(gpg(home, "--armor", "--export", uid))
+        else:
+            gpg(home, "--import", secret)
+
+        listing = gpg(home, "--with-colons", "--fingerprint", uid).decode()
+        fingerprints[key] = next(line.split(":")[9] for line in listing.splitlines()
+                                 if line.startswith("fpr:"))
+
+    return fingerprints
+
+
+def sign(home, fingerprint, data):
+    """A detached signature of `data`, as an OpenPGP packet"""
+    return gpg(home, "--local-user", fingerprint, "--digest-algo", "SHA256",
+               "--detach-sign", "--output", "-", input=data)
+
+
+def parse_packet(packet):
+    """Split an old format packet into its tag and body"""
+    assert packet[0] & 0xc0 == 0x80, "expected an old format packet"
+    length_type = packet[0] & 0x03
+    size = {0: 1, 1: 2, 2: 4}[length_type]
+    length = int.from_bytes(packet[1:1 + size], "big")
+    return (packet[0] >> 2) & 0x0f, packet[1 + size:1 + size + length]
+
+
+def new_format(tag, body):
+    """A packet with a new format header"""
+    if len(body) < 192:
+        length = bytes([len(body)])
+    elif len(body) < 8384:
+        length = bytes([((len(body) - 192) >> 8) + 192, (len(body) - 192) & 0xff])
+    else:
+        length = b"\xff" + struct.pack(">I", len(body))
+    return bytes([0xc0 | tag]) + length + body
+
+
+def old_format(tag, body):
+    """A packet with an old format header and the shortest length"""
+    if len(body) < 256:
+        return bytes([0x80 | tag << 2]) + bytes([len(body)]) + body
+    return bytes([0x81 | tag << 2]) + struct.pack(">H", len(body)) + body
+
+
+def without_issuer(body):
+    """A version 4 signature without the unhashed area, leaving only the
+    issuer fingerprint subpacket (type 33) of the hashed area"""
+    hashed_len = struct.unpack(">H", body[4:6])[0]
+    rest = body[6 + hashed_len:]
+    unhashed_len = struct.unpack(">H", rest[:2])[0]
+    return body[:6 + hashed_len] + b"\0\0" + rest[2 + unhashed_len:]
+
+
+def write_signatures(name, packet):
+    os.makedirs("signatures", exist_ok=True)
+    tag, body = parse_packet(packet)
+    variants = {
+        "old": old_format(tag, body),
+        "new": new_format(tag, body),
+        "fingerprint": new_format(tag, without_issuer(body)),
+    }
+
+    for variant, data in variants.items():
+        with open("signatures/%s-%s.sig" % (name, variant), "wb") as f:
+            f.write(data)
+
+
+def main():
+    os.makedirs("rpms", exist_ok=True)
+    for path in glob.glob("rpms/*.rpm") + glob.glob("signatures/*.sig"):
+        os.remove(path)
+
+    with tempfile.TemporaryDirectory() as home:
+        fingerprints = load_keys(home)
+
+        for name, compressor, key, files in PACKAGES:
+            archive = payload(files)
+            compressed = compress(archive, compressor)
+            main = main_header(name, compressor, files, compressed)
+
+            packet = None
+            if key is not None:
+                packet = sign(home, fingerprints[key], main)
+                write_signatures(key, packet)
+
+            with open("rpms/%s-1.0-1.noarch.rpm" % name, "wb") as f:
+                f.write(lead("%s-1.0-1" % name))
+                f.write(signature_header(main, compressed, len(archive), packet))
+                f.write(main)
+                f.write(compressed)
+
+    for key, fingerprint in sorted(fingerprints.items()):
+        print("%s: %s (key ID %s)" % (key, fingerprint, fingerprint[-16:].lower()))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,332 @@
+#!/usr/bin/env python3
+"""Generate the synthetic SQLite rpmdbs in testdata/sqlite{,-updated}
+
+Writes headers for a handful of packages using the same schema as rpm's
+sqlite backend (rpm >= 4.16). Run from the testdata directory.
+
+As rpm does on install, the tags added at install time and those from the
+signature header are stored after the immutable region, and every index
+table is populated.
+
+testdata/sqlite-updated is the same system after a later transaction which
+rebuilt, upgraded, removed and added packages.
+"""
+
+import hashlib
+import os
+import sqlite3
+import struct
+
+# Tag numbers and types (from rpmtag.h)
+HEADERIMMUTABLE = 63
+HEADERI18NTABLE = 100
+SIGMD5, SHA1HEADER, SHA256HEADER = 261, 269, 273
+NAME, VERSION, RELEASE, EPOCH = 1000, 1001, 1002, 1003
+SUMMARY, DESCRIPTION, BUILDTIME = 1004, 1005, 1006
+INSTALLTIME, LICENSE, OS, ARCH = 1008, 1014, 1021, 1022
+FILESIZES, FILESTATES, FILEMODES = 1028, 1029, 1030
+FILEMTIMES, FILEFLAGS, FILEUSERNAME, FILEGROUPNAME = 1034, 1037, 1039, 1040
+PROVIDENAME, REQUIREFLAGS, REQUIRENAME, REQUIREVERSION = 1047, 1048, 1049, 1050
+PROVIDEFLAGS, PROVIDEVERSION = 1112, 1113
+DIRINDEXES, BASENAMES, DIRNAMES = 1116, 1117, 1118
+INSTALLTID = 1128
+
+CHAR, INT16, INT32, STRING, BIN, STRING_ARRAY, I18NSTRING = 1, 3, 4, 6, 7, 8, 9
+
+ALIGNMENT = {INT16: 2, INT32: 4}
+
+# Dependency flags (from rpmds.h)
+RPMSENSE_LESS, RPMSENSE_EQUAL, RPMSENSE_RPMLIB = 2, 8, 1 << 24
+
+# Magic of header blobs, which is part of the header digests
+HEADER_MAGIC = b"\x8e\xad\xe8\x01\0\0\0\0"
+
+# Transactions the packages were installed in
+INSTALL_TID = 1700000000
+UPDATE_TID = 1700100000
+
+PACKAGES = [
+    # name, epoch, version, release, arch, license, summary, buildtime
+    ("basesystem", None, "11", "13.el9", "noarch", "Public Domain",
+     "The skeleton package which defines a simple Red Hat Enterprise Linux system",
+     1628000000),
+    ("bash", None, "5.1.8", "6.el9", "x86_64", "GPLv3+",
+     "The GNU Bourne Again shell", 1660000000),
+    ("tzdata", None, "2023c", "1.el9", "noarch", "Public Domain",
+     "Timezone data", 1680000000),
+    ("openssl-libs", 1, "3.0.7", "16.el9", "x86_64", "ASL 2.0",
+     "A general purpose cryptography library with TLS implementation",
+     1690000000),
+]
+
+# Dependencies and files of each package, by name. The packages in the
+# conditions and exclusions of the rich dependencies aren't required.
+RICH = ("rpmlib(RichDependencies)", RPMSENSE_RPMLIB | RPMSENSE_LESS | RPMSENSE_EQUAL,
+        "4.12.0-1")
+
+CONTENTS = {
+    "basesystem": {
+        "provides": [],
+        "requires": [RICH, ("(bash if openssl-libs)", 0, "")],
+        "files": [],
+    },
+    "bash": {
+        "provides": [("/bin/sh", 0, "")],
+        "requires": [RICH, ("(tzdata if basesystem else openssl-libs)", 0, "")],
+        "files": ["/usr/bin/bash", "/usr/bin/sh"],
+    },
+    "openssl-libs": {
+        "provides": [("libcrypto.so.3%s", 0, ""), ("libssl.so.3%s", 0, "")],
+        "requires": [("/usr/bin/sh", 0, ""), ("libc.so.6%s", 0, "")],
+        "files": ["/usr/%s/libcrypto.so.3", "/usr/%s/libssl.so.3"],
+    },
+    "tzdata": {
+        "provides": [],
+        "requires": [RICH, ("(bash without openssl-libs)", 0, "")],
+        "files": ["/usr/share/zoneinfo/UTC"],
+    },
+}
+
+# Marker of arch-specific provides, by arch
+ISA = {"x86_64": "x86-64", "i686": "x86-32"}
+
+# After the update: basesystem is unchanged, bash rebuilt, tzdata removed,
+# and openssl-libs upgraded along with its 32-bit multilib package added
+UPDATED_PACKAGES = [
+    (PACKAGES[0], INSTALL_TID, ""),
+    (PACKAGES[1], UPDATE_TID, "rebuild"),
+    (("openssl-libs", 1, "3.0.7", "18.el9", "x86_64", "ASL 2.0",
+      PACKAGES[3][6], 1700000000), UPDATE_TID, ""),
+    (("openssl-libs", 1, "3.0.7", "18.el9", "i686", "ASL 2.0",
+      PACKAGES[3][6], 1700000000), UPDATE_TID, ""),
+]
+
+
+def encode(kind, value):
+    if kind == CHAR:
+        return bytes(value), len(value)
+    if kind == INT16:
+        return b"".join(struct.pack(">h", v) for v in value), len(value)
+    if kind == INT32:
+        return b"".join(struct.pack(">i", v) for v in value), len(value)
+    if kind == STRING:
+        return value.encode() + b"\0", 1
+    if kind in (STRING_ARRAY, I18NSTRING):
+        return b"".join(v.encode() + b"\0" for v in value), len(value)
+    if kind == BIN:
+        return value, len(value)
+    raise ValueError(kind)
+
+
+def add_entries(tags, entries, data):
+    """Append index entries and data for the given tags, in tag order"""
+    for tag, kind, value in sorted(tags):
+        data += b"\0" * (-len(data) % ALIGNMENT.get(kind, 1))
+        encoded, count = encode(kind, value)
+        entries.append(struct.pack(">iiii", tag, kind, len(data), count))
+        data += encoded
+
+    return data
+
+
+def header_blob(tags, install_tags):
+    """Build a header blob (without magic) with an immutable region holding
+    `tags`, followed by `install_tags` and the header digests. Returns the
+    blob and its digest tags."""
+    entries = []
+    data = add_entries(tags, entries, b"")
+
+    ril = len(entries) + 1
+    region = struct.pack(">iiii", HEADERIMMUTABLE, BIN, len(data), 16)
+    data += struct.pack(">iiii", HEADERIMMUTABLE, BIN, -ril * 16, 16)
+    entries.insert(0, region)
+
+    # The digests cover the region as it would be in the package file
+    signed = HEADER_MAGIC + struct.pack(">ii", ril, len(data)) + b"".join(entries) + data
+    digests = [
+        (SHA1HEADER, STRING, hashlib.sha1(signed).hexdigest()),
+        (SHA256HEADER, STRING, hashlib.sha256(signed).hexdigest()),
+    ]
+
+    data = add_entries(install_tags + digests, entries, data)
+    blob = struct.pack(">ii", len(entries), len(data)) + b"".join(entries) + data
+    return blob, digests
+
+
+def evr(epoch, version, release):
+    return "%s%s-%s" % ("" if epoch is None else "%d:" % epoch, version, release)
+
+
+def package_contents(name, epoch, version, release, arch):
+    """Provides, requires and files of a package, filled in for its arch"""
+    contents = CONTENTS[name]
+    marker, libdir = ("()(64bit)", "lib64") if arch == "x86_64" else ("", "lib")
+
+    provides = [(n.replace("%s", marker), f, v) for n, f, v in contents["provides"]]
+    provides.append((name, RPMSENSE_EQUAL, evr(epoch, version, release)))
+
+    if arch in ISA:
+        provides.append(("%s(%s)" % (name, ISA[arch]), RPMSENSE_EQUAL,
+                         evr(epoch, version, release)))
+
+    requires = [(n.replace("%s", marker), f, v) for n, f, v in contents["requires"]]
+    files = [f.replace("%s", libdir) for f in contents["files"]]
+    return provides, requires, files
+
+
+def file_tags(files, mtime):
+    dirnames = sorted({os.path.dirname(f) + "/" for f in files})
+    modes = [0o100755 if "/bin/" in f else 0o100644 for f in files]
+    # Modes are unsigned, but stored in a signed 16-bit integer
+    modes = [m - 0x10000 if m >= 0x8000 else m for m in modes]
+
+    return [
+        (FILESIZES, INT32, [1024] * len(files)),
+        (FILEMODES, INT16, modes),
+        (FILEMTIMES, INT32, [mtime] * len(files)),
+        (FILEFLAGS, INT32, [0] * len(files)),
+        (FILEUSERNAME, STRING_ARRAY, ["root"] * len(files)),
+        (FILEGROUPNAME, STRING_ARRAY, ["root"] * len(files)),
+        (DIRINDEXES, INT32, [dirnames.index(os.path.dirname(f) + "/") for f in files]),
+        (BASENAMES, STRING_ARRAY, [os.path.basename(f) for f in files]),
+        (DIRNAMES, STRING_ARRAY, dirnames),
+    ]
+
+
+def package_tags(name, epoch, version, release, arch, license, summary,
+                 buildtime, tid=INSTALL_TID, build=""):
+    """Tags of the package header, and those added when it's installed"""
+    # A rebuild is built later, changing the header and its digests
+    if build:
+        buildtime += 86400
+
+    # Stand-in for the MD5 of the header and payload, which changes on rebuild
+    sigmd5 = hashlib.md5(
+        ("%s-%s-%s.%s%s" % (name, version, release, arch, build)).encode()
+    ).digest()
+
+    provides, requires, files = package_contents(name, epoch, version, release, arch)
+
+    tags = [
+        (HEADERI18NTABLE, STRING_ARRAY, ["C"]),
+        (NAME, STRING, name),
+        (VERSION, STRING, version),
+        (RELEASE, STRING, release),
+        (SUMMARY, I18NSTRING, [summary]),
+        (DESCRIPTION, I18NSTRING, [summary + "."]),
+        (BUILDTIME, INT32, [buildtime]),
+        (LICENSE, STRING, license),
+        (OS, STRING, "linux"),
+        (ARCH, STRING, arch),
+        (PROVIDENAME, STRING_ARRAY, [n for n, _, _ in provides]),
+        (PROVIDEFLAGS, INT32, [f for _, f, _ in provides]),
+        (PROVIDEVERSION, STRING_ARRAY, [v for _, _, v in provides]),
+        (REQUIRENAME, STRING_ARRAY, [n for n, _, _ in requires]),
+        (REQUIREFLAGS, INT32, [f for _, f, _ in requires]),
+        (REQUIREVERSION, STRING_ARRAY, [v for _, _, v in requires]),
+    ]
+
+    if files:
+        tags += file_tags(files, buildtime)
+
+    if epoch is not None:
+        tags.append((EPOCH, INT32, [epoch]))
+
+    install_tags = [
+        (SIGMD5, BIN, sigmd5),
+        (INSTALLTIME, INT32, [tid]),
+        (INSTALLTID, INT32, [tid]),
+    ]
+
+    if files:
+        install_tags.append((FILESTATES, CHAR, [0] * len(files)))
+
+    return tags, install_tags
+
+
+# Index tables created by rpm, the tags they index, and whether their keys
+# are strings (any other tags have empty indexes here)
+INDEXES = [
+    ("Name", NAME, True), ("Basenames", BASENAMES, True), ("Group", None, True),
+    ("Requirename", REQUIRENAME, True), ("Providename", PROVIDENAME, True),
+    ("Conflictname", None, True), ("Obsoletename", None, True),
+    ("Triggername", None, True), ("Dirnames", DIRNAMES, True),
+    ("Installtid", INSTALLTID, False), ("Sigmd5", SIGMD5, False),
+    ("Sha1header", SHA1HEADER, True), ("Filetriggername", None, True),
+    ("Transfiletriggername", None, True), ("Recommendname", None, True),
+    ("Suggestname", None, True), ("Supplementname", None, True),
+    ("Enhancename", None, True),
+]
+
+
+def index_keys(tag, tags):
+    """Keys of a header in the index of the given tag, with the index of
+    each in the tag's data. Duplicates are indexed once, as rpm does."""
+    values = [value for t, _, value in tags if t == tag]
+
+    if not values:
+        return []
+
+    value = values[0]
+
+    if isinstance(value, (str, bytes)):
+        return [(value, 0)]
+    if tag == INSTALLTID:
+        # Integer keys are stored in host (little endian) byte order
+        return [(struct.pack("<I", value[0]), 0)]
+
+    keys = []
+
+    for i, key in enumerate(value):
+        if key not in [k for k, _ in keys]:
+            keys.append((key, i))
+
+    return keys
+
+
+def write_db(directory, packages):
+    path = os.path.join(directory, "rpmdb.sqlite")
+    os.makedirs(directory, exist_ok=True)
+
+    if os.path.exists(path):
+        os.remove(path)
+
+    db = sqlite3.connect(path)
+    db.execute(
+        "CREATE TABLE 'Packages' ("
+        "hnum INTEGER PRIMARY KEY AUTOINCREMENT, blob BLOB NOT NULL)"
+    )
+
+    for table, _, text in INDEXES:
+        db.execute(
+            "CREATE TABLE '%s' (key '%s' NOT NULL, hnum INTEGER NOT NULL, "
+            "idx INTEGER NOT NULL, FOREIGN KEY (hnum) REFERENCES 'Packages'(hnum))"
+            % (table, "TEXT" if text else "BLOB")
+        )
+        db.execute("CREATE INDEX '%s_key_idx' ON '%s'(key ASC)" % (table, table))
+        db.execute("CREATE INDEX '%s_hnum_idx' ON '%s'(hnum ASC)" % (table, table))
+
+    for package, tid, build in packages:
+        tags, install_tags = package_tags(*package, tid=tid, build=build)
+        blob, digests = header_blob(tags, install_tags)
+        hnum = db.execute("INSERT INTO Packages (blob) VALUES (?)", (blob,)).lastrowid
+        tags = tags + install_tags + digests
+
+        for table, tag, _ in INDEXES:
+            for key, idx in index_keys(tag, tags):
+                db.execute(
+                    "INSERT INTO '%s' VALUES (?, ?, ?)" % table, (key, hnum, idx)
+                )
+
+    db.execute("PRAGMA user_version = 1")
+    db.commit()
+    db.close()
+
+
+def main():
+    write_db("sqlite", [(package, INSTALL_TID, "") for package in PACKAGES])
+    write_db("sqlite-updated", UPDATED_PACKAGES)
+
+
+if __name__ == "__main__":
+    main()
@@ -1,4 +1,9 @@
-use librpm::{config::set_db_path, db::installed_packages, Package};
+use librpm::graph::DependencyGraph;
+use librpm::history;
+use librpm::package::ScriptletKind;
+use librpm::{config::set_db_path, db, db::installed_packages, Index, Package};
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime};
 
 mod common;
 
@@ -24,3 +29,310 @@ fn test_centos_7_rpm_database() {
         "This package contains the getfacl and setfacl utilities needed for\nmanipulating access control lists."
     );
 }
+
+#[test]
+fn test_centos_7_metadata() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("centos7")).unwrap();
+
+    let package = installed_packages().find(|p| p.name() == "acl").unwrap();
+
+    assert_eq!(package.vendor(), Some("CentOS"));
+    assert_eq!(package.group(), Some("System Environment/Base"));
+    assert_eq!(package.sourcerpm(), Some("acl-2.2.51-15.el7.src.rpm"));
+    assert_eq!(package.os(), Some("linux"));
+    assert_eq!(package.payload_format(), Some("cpio"));
+    assert_eq!(package.payload_compressor(), Some("xz"));
+    assert!(package.url().is_some());
+    assert!(package.buildhost().is_some());
+    assert!(package.rpmversion().is_some());
+    assert!(package.size().unwrap() > 0);
+    assert!(package.installtime().unwrap() >= package.buildtime());
+}
+
+#[test]
+fn test_centos_7_package_identity() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("centos7")).unwrap();
+
+    let packages: Vec<Package> = installed_packages().collect();
+    let records: HashSet<u32> = packages.iter().map(|p| p.record().unwrap()).collect();
+    assert_eq!(records.len(), packages.len());
+
+    for package in &packages[..10] {
+        assert_eq!(package.hdrid().unwrap().len(), 40);
+        assert_eq!(package.pkgid().unwrap().len(), 32);
+        assert!(package.installtid().is_some());
+
+        let record = package.record().unwrap();
+        assert_eq!(db::find_by_record(record).as_ref(), Some(package));
+
+        let hdrid = package.hdrid().unwrap();
+        assert_eq!(db::find_by_hdrid(hdrid).as_ref(), Some(package));
+    }
+
+    assert!(db::find_by_record(u32::MAX).is_none());
+    assert!(db::find_by_hdrid(&"0".repeat(40)).is_none());
+}
+
+#[test]
+fn test_centos_7_find_by_label() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("centos7")).unwrap();
+
+    for label in &[
+        "acl",
+        "acl-2.2.51",
+        "acl-2.2.51-15.el7",
+        "acl-2.2.51-15.el7.x86_64",
+        "acl-0:2.2.51-15.el7.x86_64",
+        "acl-2.2.51-15.el7.x86_64.rpm",
+    ] {
+        let found: Vec<Package> = db::find_by_label(label).collect();
+        assert_eq!(found.len(), 1, "{}", label);
+        assert_eq!(found[0].nevra(), "acl-2.2.51-15.el7.x86_64");
+    }
+
+    for label in &[
+        "acl-1:2.2.51-15.el7",
+        "acl-2.2.51-15.el7.i686",
+        "acl-9.9",
+        "acl-2.2.51-15.el7\0.x86_64.rpm",
+        "acl\0",
+    ] {
+        assert!(db::find_by_label(label).next().is_none(), "{}", label);
+    }
+}
+
+fn names(packages: &[Package]) -> Vec<&str> {
+    let mut names: Vec<&str> = packages.iter().map(|p| p.name()).collect();
+    names.sort_unstable();
+    names
+}
+
+#[test]
+fn test_centos_7_what_provides() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("centos7")).unwrap();
+
+    assert_eq!(names(&db::what_provides("bash").unwrap()), ["bash"]);
+    assert_eq!(
+        names(&db::what_provides("/usr/bin/bash").unwrap()),
+        ["bash"]
+    );
+    assert_eq!(names(&db::what_provides("/bin/sh").unwrap()), ["bash"]);
+    assert_eq!(
+        names(&db::what_provides("libc.so.6()(64bit)").unwrap()),
+        ["glibc"]
+    );
+    assert_eq!(
+        names(&db::what_provides("glibc >= 2.17").unwrap()),
+        ["glibc"]
+    );
+    assert!(db::what_provides("glibc > 3").unwrap().is_empty());
+    assert!(db::what_provides("no-such-capability").unwrap().is_empty());
+
+    let err = db::what_provides("glibc >>> 2").unwrap_err();
+    assert_eq!(err.kind(), librpm::error::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_centos_7_what_requires() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("centos7")).unwrap();
+
+    assert!(!db::what_requires("/bin/sh").unwrap().is_empty());
+
+    let requires_libc = db::what_requires("libc.so.6()(64bit)").unwrap();
+    assert!(requires_libc.len() > 50);
+    assert!(names(&requires_libc).contains(&"bash"));
+
+    // glibc requires the glibc-common of the same version
+    let requires_common = db::what_requires("glibc-common").unwrap();
+    assert!(names(&requires_common).contains(&"glibc"));
+    let requires_newer = db::what_requires("glibc-common > 99").unwrap();
+    assert!(!names(&requires_newer).contains(&"glibc"));
+}
+
+#[test]
+fn test_centos_7_nested_queries() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("centos7")).unwrap();
+
+    let mut count = 0;
+
+    for package in installed_packages() {
+        assert!(
+            Index::Name.find(package.name()).any(|p| p == package),
+            "{}",
+            package
+        );
+        count += 1;
+    }
+
+    assert_eq!(count, 148);
+}
+
+#[test]
+fn test_centos_7_dependency_graph() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("centos7")).unwrap();
+
+    let graph = DependencyGraph::installed();
+    assert_eq!(graph.packages().len(), 148);
+
+    let find = |name: &str| graph.packages().iter().find(|p| p.name() == name).unwrap();
+    let names = |packages: Vec<&Package>| -> HashSet<String> {
+        packages.iter().map(|p| p.name().to_owned()).collect()
+    };
+
+    let bash = find("bash");
+    let glibc = find("glibc");
+    assert!(names(graph.requires(bash)).contains("glibc"));
+    assert!(names(graph.required_by(glibc)).contains("bash"));
+    assert!(!names(graph.requires(glibc)).contains("glibc"));
+    assert!(names(graph.transitive_requires(bash)).contains("glibc-common"));
+    assert!(graph.transitive_required_by(glibc).len() > 50);
+
+    let leaves = names(graph.leaves());
+    assert!(!leaves.is_empty());
+    assert!(!leaves.contains("glibc"));
+    assert!(!leaves.contains("bash"));
+
+    // glibc and glibc-common require each other
+    let components = graph.strongly_connected_components();
+    assert_eq!(components.iter().map(Vec::len).sum::<usize>(), 148);
+    let glibc_component = components.iter().find(|c| c.contains(&glibc)).unwrap();
+    assert!(glibc_component.iter().any(|p| p.name() == "glibc-common"));
+
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph"));
+    assert!(dot.contains(&format!("\"{}\" -> \"{}\";", bash.nevra(), glibc.nevra())));
+}
+
+#[test]
+fn test_centos_7_history() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("centos7")).unwrap();
+
+    let transactions = history::transactions();
+    assert!(!transactions.is_empty());
+
+    let count: usize = transactions.iter().map(|t| t.packages().len()).sum();
+    assert_eq!(count, 148);
+
+    for pair in transactions.windows(2) {
+        assert!(pair[0].time() < pair[1].time());
+    }
+
+    for transaction in &transactions {
+        for package in transaction.packages() {
+            assert_eq!(package.installtid(), Some(transaction.id()));
+        }
+    }
+
+    let last = transactions.last().unwrap();
+    let everything = history::between(SystemTime::UNIX_EPOCH, last.time() + Duration::from_secs(1));
+    assert_eq!(everything, transactions);
+    assert_eq!(
+        history::between(last.time(), last.time() + Duration::from_secs(1)),
+        std::slice::from_ref(last)
+    );
+}
+
+#[test]
+fn test_centos_7_changelog() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("centos7")).unwrap();
+
+    let package = installed_packages().find(|p| p.name() == "acl").unwrap();
+    let changelog = package.changelog().unwrap();
+
+    assert!(!changelog.is_empty());
+    assert!(changelog.iter().all(|entry| !entry.author().is_empty()));
+    assert!(changelog
+        .windows(2)
+        .all(|pair| pair[0].timestamp() >= pair[1].timestamp()));
+
+    let latest = changelog[0].timestamp();
+    assert!(!package.changelog_since(latest).unwrap().is_empty());
+    assert!(package
+        .changelog_since(latest + Duration::from_secs(1))
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        package.changelog_since(SystemTime::UNIX_EPOCH).unwrap(),
+        changelog
+    );
+}
+
+#[test]
+fn test_centos_7_scriptlets() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("centos7")).unwrap();
+
+    let glibc = installed_packages().find(|p| p.name() == "glibc").unwrap();
+
+    let postun = glibc.scriptlet(ScriptletKind::PostUn).unwrap().unwrap();
+    assert_eq!(postun.interpreter(), "/sbin/ldconfig");
+    assert!(postun.args().is_empty());
+    assert_eq!(postun.body(), None);
+
+    for package in installed_packages() {
+        for scriptlet in package.scriptlets().unwrap() {
+            assert!(!scriptlet.interpreter().is_empty(), "{}", package);
+        }
+    }
+}
+
+#[test]
+fn test_centos_7_triggers() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("centos7")).unwrap();
+
+    let triggering: Vec<(Package, String)> = installed_packages()
+        .flat_map(|p| {
+            let names: Vec<String> = p
+                .triggers()
+                .unwrap()
+                .iter()
+                .map(|t| t.condition().name().to_owned())
+                .collect();
+            names.into_iter().map(move |n| (p.clone(), n))
+        })
+        .collect();
+
+    assert!(!triggering.is_empty());
+    assert!(Index::Triggers.find("systemd").next().is_some());
+
+    for (package, name) in triggering {
+        assert!(Index::Triggers.find(&name).any(|p| p == package));
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_centos_7_serde() {
+    use librpm::header::TagData;
+    use std::collections::BTreeMap;
+
+    common::configure();
+    set_db_path(&common::get_assets_path().join("centos7")).unwrap();
+
+    let package = installed_packages().find(|p| p.name() == "acl").unwrap();
+    let json = serde_json::to_string(&package).unwrap();
+    let decoded: Package = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, package);
+
+    let header = Index::Name.find("acl").headers().next().unwrap();
+    let json = serde_json::to_string(&header).unwrap();
+    let map: BTreeMap<String, TagData> = serde_json::from_str(&json).unwrap();
+    assert_eq!(map["Name"], TagData::Str("acl".to_owned()));
+    assert_eq!(map["Version"], TagData::Str("2.2.51".to_owned()));
+    assert!(map["Changelogtime"].as_int32_array().is_some());
+
+    let graph = DependencyGraph::installed();
+    let json: serde_json::Value = serde_json::to_value(&graph).unwrap();
+    assert_eq!(json["packages"].as_array().unwrap().len(), 148);
+    assert!(!json["edges"].as_array().unwrap().is_empty());
+}
@@ -17,8 +17,11 @@
 
 //! librpm.rs integration tests
 
+use librpm::arch::{Arch, Os};
 use librpm::db::installed_packages;
-use librpm::Package;
+use librpm::error::ErrorKind;
+use librpm::{MacroContext, Package};
+use std::path::Path;
 use std::process::Command;
 
 mod common;
@@ -82,3 +85,61 @@ fn test_against_installed_packages() {
         assert_eq!(expected.summary, found.summary());
     }
 }
+
+#[test]
+fn test_installed_packages_are_compatible() {
+    common::configure();
+
+    let host = Arch::current().expect("no configured arch");
+    assert!(host.is_compatible_with(&host));
+    assert!(Arch::new("noarch").is_compatible_with(&host));
+    assert!(Os::current().expect("no configured OS").is_compatible());
+
+    for package in installed_packages() {
+        if let Some(arch) = package.arch().map(Arch::from) {
+            // gpg-pubkey entries have no real architecture
+            if package.name() != "gpg-pubkey" {
+                assert!(arch.is_compatible_with(&host), "{}", package);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_open_non_package_file() {
+    common::configure();
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let err = Package::open(&path).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Header);
+
+    // Without `logging::capture` or `logging::init`, librpm still prints its
+    // messages itself, and the last one is kept with the error
+    assert!(err
+        .log()
+        .iter()
+        .any(|m| m.level() == log::Level::Error && m.text().contains("Cargo.toml")));
+}
+
+#[test]
+#[cfg(feature = "librpm-4-14")]
+fn test_invalid_macro_names() {
+    common::configure();
+
+    let err = MacroContext::default().pop("nul\0byte").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let err = MacroContext::default()
+        .define("nul\0byte 1", 0)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Config);
+}
+
+#[test]
+fn test_payload_of_installed_package() {
+    common::configure();
+
+    let package = installed_packages().next().unwrap();
+    assert!(package.path().is_none());
+    assert!(package.payload().is_err());
+}
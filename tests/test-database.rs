@@ -0,0 +1,117 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for database maintenance operations
+
+use librpm::config::set_db_path;
+use librpm::db::{Backend, Database};
+use librpm::error::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
+
+mod common;
+
+/// Location of the database relative to the root directories used here
+const DB_PATH: &str = "/var/lib/rpm";
+
+fn configure() {
+    common::configure();
+    set_db_path(Path::new(DB_PATH)).unwrap();
+}
+
+/// Create an empty root directory for a test
+fn temp_root(name: &str) -> PathBuf {
+    let root = env::temp_dir().join(format!("librpm-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    root
+}
+
+/// Create a root directory containing a copy of a fixture database
+fn copy_fixture(name: &str, fixture: &str) -> PathBuf {
+    let root = temp_root(name);
+    let db = root.join(DB_PATH.trim_start_matches('/'));
+    fs::create_dir_all(&db).unwrap();
+
+    for entry in fs::read_dir(common::get_assets_path().join(fixture)).unwrap() {
+        let entry = entry.unwrap();
+        fs::copy(entry.path(), db.join(entry.file_name())).unwrap();
+    }
+
+    root
+}
+
+#[test]
+fn test_init_database() {
+    configure();
+    let root = temp_root("init");
+
+    Database::init(&root).unwrap();
+
+    let db = root.join(DB_PATH.trim_start_matches('/'));
+    assert!(fs::read_dir(db).unwrap().next().is_some());
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_rebuild_and_verify_database() {
+    configure();
+    let root = copy_fixture("rebuild", "centos7");
+
+    Database::verify(&root).unwrap();
+    Database::rebuild(&root).unwrap();
+    Database::verify(&root).unwrap();
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_detect_backend() {
+    let assets = common::get_assets_path();
+
+    assert_eq!(
+        Backend::detect(&assets.join("centos7")),
+        Some(Backend::BerkeleyDb)
+    );
+    assert_eq!(
+        Backend::detect(&assets.join("sqlite")),
+        Some(Backend::Sqlite)
+    );
+    assert_eq!(Backend::detect(&assets), None);
+}
+
+#[test]
+fn test_convert_database() {
+    configure();
+    let root = copy_fixture("convert", "centos7");
+    let db = root.join(DB_PATH.trim_start_matches('/'));
+
+    Database::convert(&root, Backend::Sqlite).unwrap();
+    assert_eq!(Backend::detect(&db), Some(Backend::Sqlite));
+    Database::verify(&root).unwrap();
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_relative_root() {
+    configure();
+
+    let err = Database::init(Path::new("relative/root")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
@@ -0,0 +1,95 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for comparing dependencies
+
+use librpm::error::ErrorKind;
+use librpm::package::{Dependency, DependencyFlags};
+
+mod common;
+
+fn overlaps(a: &str, b: &str) -> bool {
+    let a = Dependency::parse(a).unwrap();
+    let b = Dependency::parse(b).unwrap();
+    a.overlaps(&b).unwrap()
+}
+
+#[test]
+fn test_dependency_overlaps() {
+    common::configure();
+
+    assert!(overlaps("glibc >= 2.34", "glibc = 2.34-60.el9"));
+    assert!(overlaps("glibc", "glibc = 2.34-60.el9"));
+    assert!(overlaps("glibc >= 2.34", "glibc"));
+    assert!(!overlaps("glibc >= 2.35", "glibc = 2.34-60.el9"));
+    assert!(!overlaps("glibc < 2.34", "glibc >= 2.34"));
+    assert!(overlaps("glibc <= 2.34", "glibc >= 2.34"));
+    assert!(!overlaps("glibc", "musl"));
+
+    // Missing releases match any release
+    assert!(overlaps("foo = 1.0", "foo = 1.0-5"));
+    assert!(!overlaps("foo = 1.0-4", "foo = 1.0-5"));
+
+    // Missing epochs are 0, unless promoted
+    assert!(!overlaps("foo >= 1:1.0", "foo = 1.5"));
+    assert!(overlaps("foo >= 1:1.0", "foo = 1:1.5"));
+
+    let promoted = |a: &str, b: &str| {
+        let a = Dependency::parse(a).unwrap();
+        let b = Dependency::parse(b).unwrap();
+        a.overlaps_promoting_epoch(&b).unwrap()
+    };
+
+    assert!(promoted("foo >= 1:1.0", "foo = 1.5"));
+    assert!(promoted("foo >= 1.0", "foo = 2:1.5"));
+    assert!(!promoted("foo >= 1.0", "foo = 2:0.5"));
+    assert!(!promoted("foo >= 1:2.0", "foo = 1.5"));
+    assert!(!promoted("foo >= 2:1.0", "foo = 1:1.5"));
+    assert!(promoted("foo >= 1:1.0", "foo"));
+    assert!(promoted("foo = 1.0", "foo = 1.0-1"));
+}
+
+#[test]
+fn test_dependency_null_bytes() {
+    common::configure();
+
+    let err = Dependency::parse("foo\0bar").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = Dependency::parse("foo >= 1.0\0").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let err = Dependency::new("foo\0bar", DependencyFlags::ANY, None).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = Dependency::new("foo", DependencyFlags::EQUAL, Some("1.0\0".to_owned())).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialized_dependency_null_bytes() {
+    common::configure();
+
+    // Deserializing bypasses `Dependency::new`, so comparing fails instead
+    let json = r#"{"name": "foo\u0000bar", "flags": 0, "version": null}"#;
+    let dependency: Dependency = serde_json::from_str(json).unwrap();
+    let foo = Dependency::parse("foo").unwrap();
+
+    let err = dependency.overlaps(&foo).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    let err = foo.overlaps_promoting_epoch(&dependency).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
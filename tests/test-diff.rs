@@ -0,0 +1,128 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for comparing sets of packages
+
+use librpm::diff::{self, Change};
+use librpm::{config::set_db_path, db::installed_packages, error::ErrorKind, Package};
+use std::cmp::Ordering;
+use std::sync::Mutex;
+
+mod common;
+
+/// The database path is global, so tests must not switch it concurrently
+static DB_PATH: Mutex<()> = Mutex::new(());
+
+fn read(fixture: &str) -> Vec<Package> {
+    let _guard = DB_PATH.lock().unwrap();
+
+    common::configure();
+    set_db_path(&common::get_assets_path().join(fixture)).unwrap();
+    installed_packages().collect()
+}
+
+fn nevras(packages: &[Package]) -> Vec<String> {
+    packages.iter().map(Package::nevra).collect()
+}
+
+fn changes(changes: &[Change]) -> Vec<(String, String)> {
+    changes
+        .iter()
+        .map(|c| (c.before().nevra(), c.after().nevra()))
+        .collect()
+}
+
+#[test]
+fn test_diff_update() {
+    let diff = diff::diff(read("sqlite"), read("sqlite-updated"));
+
+    assert_eq!(nevras(diff.added()), ["openssl-libs-1:3.0.7-18.el9.i686"]);
+    assert_eq!(nevras(diff.removed()), ["tzdata-2023c-1.el9.noarch"]);
+    assert_eq!(
+        changes(diff.upgraded()),
+        [(
+            "openssl-libs-1:3.0.7-16.el9.x86_64".to_owned(),
+            "openssl-libs-1:3.0.7-18.el9.x86_64".to_owned()
+        )]
+    );
+    assert!(diff.downgraded().is_empty());
+    assert_eq!(
+        changes(diff.rebuilt()),
+        [(
+            "bash-5.1.8-6.el9.x86_64".to_owned(),
+            "bash-5.1.8-6.el9.x86_64".to_owned()
+        )]
+    );
+
+    let rebuilt = &diff.rebuilt()[0];
+    assert_ne!(rebuilt.before().pkgid(), rebuilt.after().pkgid());
+}
+
+#[test]
+fn test_diff_rollback() {
+    let diff = diff::diff(read("sqlite-updated"), read("sqlite"));
+
+    assert_eq!(nevras(diff.added()), ["tzdata-2023c-1.el9.noarch"]);
+    assert_eq!(nevras(diff.removed()), ["openssl-libs-1:3.0.7-18.el9.i686"]);
+    assert!(diff.upgraded().is_empty());
+    assert_eq!(
+        changes(diff.downgraded()),
+        [(
+            "openssl-libs-1:3.0.7-18.el9.x86_64".to_owned(),
+            "openssl-libs-1:3.0.7-16.el9.x86_64".to_owned()
+        )]
+    );
+    assert_eq!(diff.rebuilt().len(), 1);
+}
+
+#[test]
+fn test_diff_unchanged() {
+    let packages = read("sqlite");
+
+    assert!(diff::diff(packages.clone(), packages).is_empty());
+    assert!(diff::diff(vec![], vec![]).is_empty());
+}
+
+#[test]
+fn test_compare_evr() {
+    let packages = read("sqlite-updated");
+    let find = |nevra: &str| packages.iter().find(|p| p.nevra() == nevra).unwrap();
+
+    let bash = find("bash-5.1.8-6.el9.x86_64");
+    let openssl = find("openssl-libs-1:3.0.7-18.el9.x86_64");
+
+    assert_eq!(bash.compare_evr(bash), Ordering::Equal);
+    // The epoch of openssl-libs outweighs its lower version
+    assert_eq!(bash.compare_evr(openssl), Ordering::Less);
+    assert_eq!(openssl.compare_evr(bash), Ordering::Greater);
+
+    let old = read("sqlite");
+    let old_openssl = old.iter().find(|p| p.name() == "openssl-libs").unwrap();
+    assert_eq!(old_openssl.compare_evr(openssl), Ordering::Less);
+}
+
+#[test]
+fn test_packages_in_dir() {
+    common::configure();
+
+    // There are no package files in the fixtures
+    let packages = diff::packages_in_dir(common::get_assets_path()).unwrap();
+    assert!(packages.is_empty());
+
+    let err = diff::packages_in_dir(common::get_assets_path().join("nonexistent")).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Io);
+}
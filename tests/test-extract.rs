@@ -0,0 +1,173 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for extracting package payloads, using the package files in
+//! testdata/rpms
+
+use librpm::error::ErrorKind;
+use librpm::package::ExtractOptions;
+use librpm::Package;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::PathBuf;
+use std::{env, fs, process};
+
+mod common;
+
+/// Modification time of the files in the fixtures
+const MTIME: i64 = 1_600_000_000;
+
+fn open(name: &str) -> Package {
+    let path = common::get_assets_path()
+        .join("rpms")
+        .join(format!("{}-1.0-1.noarch.rpm", name));
+
+    Package::open(path).unwrap()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("librpm-extract-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_extract() {
+    common::configure();
+
+    // Signed with RSA, signed with EdDSA, and unsigned
+    for name in &["hello", "hello-xz", "hello-zstd"] {
+        let dir = temp_dir(name);
+        let options = ExtractOptions {
+            preserve_mtimes: true,
+            ..ExtractOptions::default()
+        };
+        open(name).extract_to(&dir, options).unwrap();
+
+        let hello = dir.join("usr/bin/hello");
+        assert_eq!(
+            fs::read_to_string(&hello).unwrap(),
+            "#!/bin/sh\necho 'Hello, world!'\n"
+        );
+        let meta = fs::metadata(&hello).unwrap();
+        assert_eq!(meta.permissions().mode() & 0o7777, 0o755);
+        assert_eq!(meta.mtime(), MTIME);
+
+        let readme = fs::metadata(dir.join("usr/share/doc/hello/README")).unwrap();
+        assert_eq!(readme.permissions().mode() & 0o7777, 0o644);
+
+        let hi = dir.join("usr/bin/hi");
+        assert!(fs::symlink_metadata(&hi).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_link(&hi).unwrap(), PathBuf::from("hello"));
+
+        // The contents of the hardlinks are stored with the second one
+        let greeting = fs::metadata(dir.join("usr/share/hello/greeting")).unwrap();
+        let greeting_en = fs::metadata(dir.join("usr/share/hello/greeting.en")).unwrap();
+        assert_eq!(greeting.ino(), greeting_en.ino());
+        assert_eq!(greeting.nlink(), 2);
+        assert_eq!(
+            fs::read_to_string(dir.join("usr/share/hello/greeting")).unwrap(),
+            "Hello, world!\n"
+        );
+
+        // Directory metadata is applied after their contents are extracted
+        let share = fs::metadata(dir.join("usr/share/hello")).unwrap();
+        assert!(share.is_dir());
+        assert_eq!(share.permissions().mode() & 0o7777, 0o755);
+        assert_eq!(share.mtime(), MTIME);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn test_extract_over_existing_files() {
+    common::configure();
+
+    let dir = temp_dir("existing");
+    fs::create_dir_all(dir.join("usr/bin")).unwrap();
+    fs::write(dir.join("usr/bin/hello"), "old").unwrap();
+    fs::create_dir_all(dir.join("usr/bin/hi")).unwrap();
+
+    let package = open("hello");
+    package.extract_to(&dir, ExtractOptions::default()).unwrap();
+
+    assert!(fs::read_to_string(dir.join("usr/bin/hello"))
+        .unwrap()
+        .starts_with("#!/bin/sh"));
+    assert_eq!(
+        fs::read_link(dir.join("usr/bin/hi")).unwrap(),
+        PathBuf::from("hello")
+    );
+
+    // Without preserve_mtimes, files get the time they were extracted
+    let meta = fs::metadata(dir.join("usr/bin/hello")).unwrap();
+    assert!(meta.mtime() > MTIME);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_extract_unsafe_paths() {
+    common::configure();
+
+    // An entry named `/../evil`
+    let dir = temp_dir("parent");
+    let dest = dir.join("root");
+
+    let err = open("evil-parent")
+        .extract_to(&dest, ExtractOptions::default())
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(!dir.join("evil").exists());
+    assert!(!dest.join("evil").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    // A symlink `/escape` to `../outside`, followed by `/escape/evil`
+    let dir = temp_dir("symlink");
+    let dest = dir.join("root");
+    fs::create_dir(dir.join("outside")).unwrap();
+
+    let err = open("evil-symlink")
+        .extract_to(&dest, ExtractOptions::default())
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(fs::symlink_metadata(dest.join("escape"))
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert!(!dir.join("outside/evil").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_extract_digest_mismatch() {
+    common::configure();
+
+    // The contents are still readable, but extracting them checks the digest
+    let dir = temp_dir("tampered");
+
+    let err = open("tampered")
+        .extract_to(&dir, ExtractOptions::default())
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BadSignature);
+    assert!(err.to_string().contains("usr/share/tampered"), "{}", err);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
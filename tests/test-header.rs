@@ -0,0 +1,308 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for creating and modifying headers
+
+use librpm::error::ErrorKind;
+use librpm::header::{Header, SignatureHeader, SignatureTag, Tag, TagData};
+use std::fs;
+use std::time::{Duration, UNIX_EPOCH};
+
+mod common;
+
+#[test]
+fn test_header_put_and_get() {
+    common::configure();
+
+    let mut header = Header::new();
+    header.put(Tag::NAME, "example").unwrap();
+    header.put(Tag::VERSION, "1.0").unwrap();
+    header.put(Tag::RELEASE, "1").unwrap();
+    header.put(Tag::SUMMARY, "Example package").unwrap();
+    header.put(Tag::BUILDTIME, 1_500_000_000).unwrap();
+    header.put(Tag::FILESIZES, vec![1, 2, 3]).unwrap();
+
+    assert!(header.contains(Tag::NAME));
+    assert!(!header.contains(Tag::EPOCH));
+    assert_eq!(header.get(Tag::NAME).unwrap().as_str(), Some("example"));
+    assert_eq!(
+        header.get(Tag::BUILDTIME).unwrap().to_int32(),
+        Some(1_500_000_000)
+    );
+    assert_eq!(
+        header.get(Tag::FILESIZES).unwrap().as_int32_array(),
+        Some(&[1, 2, 3][..])
+    );
+
+    let err = header.put(Tag::NAME, "again").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_header_put_single_value_into_array() {
+    common::configure();
+
+    let mut header = Header::new();
+    header.put(Tag::REQUIRENAME, "bash").unwrap();
+    header.put(Tag::FILESIZES, 42).unwrap();
+
+    assert_eq!(
+        header.get(Tag::REQUIRENAME),
+        Some(TagData::StrArray(vec!["bash".to_owned()]))
+    );
+    assert_eq!(
+        header.get(Tag::FILESIZES),
+        Some(TagData::Int32Array(vec![42]))
+    );
+
+    header.append(Tag::REQUIRENAME, "glibc").unwrap();
+    header.modify(Tag::FILESIZES, 7).unwrap();
+
+    assert_eq!(
+        header.get(Tag::REQUIRENAME),
+        Some(TagData::StrArray(vec![
+            "bash".to_owned(),
+            "glibc".to_owned()
+        ]))
+    );
+    assert_eq!(
+        header.get(Tag::FILESIZES),
+        Some(TagData::Int32Array(vec![7]))
+    );
+}
+
+#[test]
+fn test_header_type_mismatch() {
+    common::configure();
+
+    let mut header = Header::new();
+    let err = header.put(Tag::NAME, 42).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(header.put(Tag::BUILDTIME, "yesterday").is_err());
+    assert!(header.put(Tag::BUILDTIME, vec![1, 2]).is_err());
+    assert!(!header.contains(Tag::NAME));
+}
+
+#[test]
+fn test_header_append_modify_remove() {
+    common::configure();
+
+    let mut header = Header::new();
+    let names: &[&str] = &["bash", "glibc"];
+    header.append(Tag::REQUIRENAME, names).unwrap();
+    header
+        .append(Tag::REQUIRENAME, vec!["zlib".to_owned()])
+        .unwrap();
+
+    assert_eq!(
+        header.get(Tag::REQUIRENAME),
+        Some(TagData::StrArray(vec![
+            "bash".to_owned(),
+            "glibc".to_owned(),
+            "zlib".to_owned()
+        ]))
+    );
+
+    assert!(header.append(Tag::NAME, "example").is_err());
+    let err = header.modify(Tag::NAME, "example").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    header.put(Tag::NAME, "example").unwrap();
+    header.modify(Tag::NAME, "renamed").unwrap();
+    assert_eq!(header.get(Tag::NAME).unwrap().as_str(), Some("renamed"));
+
+    assert!(header.remove(Tag::NAME));
+    assert!(!header.remove(Tag::NAME));
+    assert!(header.get(Tag::NAME).is_none());
+}
+
+#[test]
+fn test_header_export_import() {
+    common::configure();
+
+    let mut header = Header::new();
+    header.put(Tag::NAME, "example").unwrap();
+    header.put(Tag::FILESIZES, vec![1, 2, 3]).unwrap();
+
+    let header = header.reload(Tag::HEADERIMMUTABLE).unwrap();
+    let blob = header.export();
+    let imported = Header::import(&blob).unwrap();
+
+    assert_eq!(imported.get(Tag::NAME), header.get(Tag::NAME));
+    assert_eq!(imported.get(Tag::FILESIZES), header.get(Tag::FILESIZES));
+    assert_eq!(imported.export(), blob);
+
+    assert!(Header::import(&blob[..blob.len() / 2]).is_err());
+}
+
+#[test]
+fn test_signature_header() {
+    common::configure();
+
+    // Signature tags share their numbers with the corresponding main header
+    // tags, e.g. SHA1 and SHA1HEADER
+    let mut header = Header::new();
+    header.put(Tag::SHA1HEADER, "0123456789abcdef").unwrap();
+    header.put(Tag::LONGSIGSIZE, 4096i64).unwrap();
+
+    let mut file = vec![0u8; 96];
+    file[..4].copy_from_slice(&[0xed, 0xab, 0xee, 0xdb]);
+    file.extend_from_slice(&[0x8e, 0xad, 0xe8, 0x01, 0, 0, 0, 0]);
+    file.extend_from_slice(&header.export());
+
+    let signature_header = SignatureHeader::read(&file[..]).unwrap();
+    assert_eq!(signature_header.sha1().as_deref(), Some("0123456789abcdef"));
+    assert_eq!(signature_header.size(), Some(4096));
+    assert!(signature_header.get(SignatureTag::MD5).is_none());
+    assert!(signature_header.signatures().is_empty());
+
+    assert!(SignatureHeader::read(&file[..100]).is_err());
+    assert!(SignatureHeader::read(&file[96..]).is_err());
+}
+
+/// Start of a package file, up to the end of a signature header holding the
+/// given signature packet as an RSA header signature
+fn signed_lead(packet: &[u8]) -> Vec<u8> {
+    let mut header = Header::new();
+    header.put(Tag::RSAHEADER, packet).unwrap();
+
+    let mut file = vec![0u8; 96];
+    file[..4].copy_from_slice(&[0xed, 0xab, 0xee, 0xdb]);
+    file.extend_from_slice(&[0x8e, 0xad, 0xe8, 0x01, 0, 0, 0, 0]);
+    file.extend_from_slice(&header.export());
+    file
+}
+
+#[test]
+fn test_signature_key_id() {
+    common::configure();
+
+    let signatures = common::get_assets_path().join("signatures");
+
+    for (key, key_id) in [("rsa", "1e1d086512b1e14b"), ("eddsa", "db6bbe372d31de2d")] {
+        // Old and new packet formats, with the issuer key ID in the unhashed
+        // area, and with only the issuer fingerprint in the hashed area
+        for variant in ["old", "new", "fingerprint"] {
+            let path = signatures.join(format!("{}-{}.sig", key, variant));
+            let packet = fs::read(&path).unwrap();
+            let header = SignatureHeader::read(&signed_lead(&packet)[..]).unwrap();
+
+            let signatures = header.signatures();
+            assert_eq!(signatures.len(), 1, "{}", path.display());
+            assert_eq!(signatures[0].tag(), SignatureTag::RSA);
+            assert_eq!(signatures[0].packet(), &packet[..]);
+
+            // Whether librpm derives the key ID from a fingerprint alone
+            // depends on its OpenPGP implementation
+            let found = signatures[0].key_id();
+            match (variant, found.as_deref()) {
+                ("fingerprint", None) => (),
+                (_, found) => assert_eq!(found, Some(key_id), "{}", path.display()),
+            }
+        }
+    }
+
+    // Truncated and malformed packets have no key ID
+    let packet = fs::read(signatures.join("rsa-new.sig")).unwrap();
+    for bad in [&packet[..20], &packet[2..], &[0x88, 0x02, 0x04, 0x00][..]] {
+        let header = SignatureHeader::read(&signed_lead(bad)[..]).unwrap();
+        assert_eq!(header.signatures()[0].key_id(), None);
+    }
+}
+
+#[test]
+fn test_signature_header_of_package() {
+    common::configure();
+
+    let rpms = common::get_assets_path().join("rpms");
+
+    let header = SignatureHeader::open(rpms.join("hello-1.0-1.noarch.rpm")).unwrap();
+    assert_eq!(header.size(), Some(1988));
+    assert_eq!(
+        header.md5().as_deref(),
+        Some("4bc425ebf5971b48e7ef46bce078eb3b")
+    );
+    assert_eq!(
+        header.sha256().as_deref(),
+        Some("2021df11787224a4caad9632c6f7374364d286c4f7e8828ff668799cf025de49")
+    );
+
+    let signatures = header.signatures();
+    assert_eq!(signatures.len(), 1);
+    assert_eq!(signatures[0].tag(), SignatureTag::RSA);
+    assert_eq!(signatures[0].pubkey_algorithm(), Some("RSA"));
+    assert_eq!(signatures[0].hash_algorithm(), Some("SHA256"));
+    assert_eq!(signatures[0].key_id().as_deref(), Some("1e1d086512b1e14b"));
+
+    let header = SignatureHeader::open(rpms.join("hello-xz-1.0-1.noarch.rpm")).unwrap();
+    let signatures = header.signatures();
+    assert_eq!(signatures.len(), 1);
+    assert_eq!(signatures[0].key_id().as_deref(), Some("db6bbe372d31de2d"));
+
+    let header = SignatureHeader::open(rpms.join("hello-zstd-1.0-1.noarch.rpm")).unwrap();
+    assert!(header.signatures().is_empty());
+    assert!(header.sha1().is_some());
+}
+
+#[test]
+fn test_header_to_package() {
+    common::configure();
+
+    let mut header = Header::new();
+    header.put(Tag::NAME, "example").unwrap();
+    header.put(Tag::VERSION, "1.0").unwrap();
+    header.put(Tag::RELEASE, "1").unwrap();
+    header.put(Tag::LICENSE, "MIT").unwrap();
+    header.put(Tag::SUMMARY, "Example package").unwrap();
+    header.put(Tag::BUILDTIME, 1_500_000_000).unwrap();
+
+    let err = header.to_package().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Header);
+
+    header.put(Tag::DESCRIPTION, "Café").unwrap();
+    let package = header.to_package().unwrap();
+    assert_eq!(package.nevra(), "example-1.0-1");
+    assert_eq!(package.description(), "Café");
+    assert_eq!(package.installtime(), None);
+
+    // Times are unsigned 32-bit, so they last past 2038
+    let mut installed = Header::import(&header.export()).unwrap();
+    installed.put(Tag::INSTALLTIME, i32::MIN).unwrap();
+    assert_eq!(
+        installed.to_package().unwrap().installtime(),
+        Some(UNIX_EPOCH + Duration::from_secs(1 << 31))
+    );
+
+    // Without a database record or file, there's nothing to read it from
+    let err = package.changelog().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    // Strings in headers needn't be UTF-8, e.g. Latin-1 "Café!"
+    let mut blob = header.export();
+    let offset = blob
+        .windows(5)
+        .position(|w| w == "Café".as_bytes())
+        .unwrap();
+    blob[offset + 3..offset + 5].copy_from_slice(b"\xe9!");
+
+    let imported = Header::import(&blob).unwrap();
+    assert_eq!(
+        imported.get(Tag::DESCRIPTION).unwrap().as_str(),
+        Some("Caf\u{fffd}!")
+    );
+    assert_eq!(imported.to_package().unwrap().description(), "Caf\u{fffd}!");
+}
@@ -0,0 +1,104 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for the installation history
+
+use librpm::history::{self, Transaction};
+use librpm::{config::set_db_path, db};
+use std::time::{Duration, UNIX_EPOCH};
+
+mod common;
+
+/// Transactions of the updated SQLite fixture (see `testdata/mksqlite.py`)
+const INSTALL_TID: u32 = 1700000000;
+const UPDATE_TID: u32 = 1700100000;
+
+fn configure() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("sqlite-updated")).unwrap();
+}
+
+fn nevras(transaction: &Transaction) -> Vec<String> {
+    transaction.packages().iter().map(|p| p.nevra()).collect()
+}
+
+#[test]
+fn test_history_transactions() {
+    configure();
+
+    let transactions = history::transactions();
+    let ids: Vec<u32> = transactions.iter().map(Transaction::id).collect();
+    assert_eq!(ids, [INSTALL_TID, UPDATE_TID]);
+
+    assert_eq!(nevras(&transactions[0]), ["basesystem-11-13.el9.noarch"]);
+    assert_eq!(
+        nevras(&transactions[1]),
+        [
+            "bash-5.1.8-6.el9.x86_64",
+            "openssl-libs-1:3.0.7-18.el9.i686",
+            "openssl-libs-1:3.0.7-18.el9.x86_64",
+        ]
+    );
+
+    let update = &transactions[1];
+    assert_eq!(
+        update.time(),
+        UNIX_EPOCH + Duration::from_secs(UPDATE_TID.into())
+    );
+
+    for package in update.packages() {
+        assert_eq!(package.installtid(), Some(UPDATE_TID));
+        assert_eq!(
+            db::find_by_record(package.record().unwrap()).as_ref(),
+            Some(package)
+        );
+    }
+}
+
+#[test]
+fn test_history_between() {
+    configure();
+
+    let install = UNIX_EPOCH + Duration::from_secs(INSTALL_TID.into());
+    let update = UNIX_EPOCH + Duration::from_secs(UPDATE_TID.into());
+    let second = Duration::from_secs(1);
+
+    let ids = |transactions: Vec<Transaction>| -> Vec<u32> {
+        transactions.iter().map(Transaction::id).collect()
+    };
+
+    assert_eq!(ids(history::between(install, update)), [INSTALL_TID]);
+    assert_eq!(
+        ids(history::between(install, update + second)),
+        [INSTALL_TID, UPDATE_TID]
+    );
+    assert_eq!(
+        ids(history::between(install + second, update + second)),
+        [UPDATE_TID]
+    );
+
+    // Transaction IDs are whole seconds
+    let millisecond = Duration::from_millis(1);
+    assert!(history::between(update + millisecond, update + second).is_empty());
+    assert_eq!(
+        ids(history::between(update - millisecond, update + millisecond)),
+        [UPDATE_TID]
+    );
+
+    assert!(history::between(update, install).is_empty());
+    assert!(history::between(UNIX_EPOCH, install).is_empty());
+}
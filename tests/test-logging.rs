@@ -0,0 +1,62 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for capturing librpm's log messages
+
+use librpm::{logging, Package};
+use std::path::Path;
+
+mod common;
+
+#[test]
+fn test_capture_messages() {
+    common::configure();
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let (result, messages) = logging::capture(|| Package::open(&path));
+
+    assert!(result.is_err());
+    assert!(messages
+        .iter()
+        .any(|m| m.level() == log::Level::Error && m.text().contains("Cargo.toml")));
+
+    // Warnings from calls which succeed aren't lost either, e.g. for a
+    // package signed with a key which isn't imported
+    let signed = common::get_assets_path().join("rpms/hello-1.0-1.noarch.rpm");
+    let (result, messages) = logging::capture(|| Package::open(&signed));
+    assert!(result.is_ok());
+    assert!(messages
+        .iter()
+        .any(|m| m.level() == log::Level::Warn && m.text().contains("NOKEY")));
+
+    // Nothing is captured outside of `capture`
+    let (_, messages) = logging::capture(|| ());
+    assert!(messages.is_empty());
+
+    // The verbosity is global, so it's tested here rather than in parallel
+    logging::set_verbosity(log::LevelFilter::Off);
+    let (result, messages) = logging::capture(|| Package::open(&path));
+    assert!(result.is_err());
+    assert!(messages.is_empty());
+
+    logging::set_verbosity(log::LevelFilter::Error);
+    let (_, messages) = logging::capture(|| Package::open(&path));
+    assert!(messages.iter().all(|m| m.level() == log::Level::Error));
+    assert!(!messages.is_empty());
+
+    logging::set_verbosity(log::LevelFilter::Info);
+}
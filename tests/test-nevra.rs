@@ -0,0 +1,125 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for parsing package labels
+
+use librpm::error::ErrorKind;
+use librpm::package::Nevra;
+
+fn parts(
+    label: &str,
+) -> (
+    String,
+    Option<i32>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+) {
+    let nevra = Nevra::parse(label).unwrap();
+
+    (
+        nevra.name().to_owned(),
+        nevra.epoch(),
+        nevra.version().map(str::to_owned),
+        nevra.release().map(str::to_owned),
+        nevra.arch().map(str::to_owned),
+    )
+}
+
+#[test]
+fn test_parse_full_label() {
+    let nevra = Nevra::parse("bash-5.1.8-4.el9.x86_64").unwrap();
+
+    assert_eq!(nevra.name(), "bash");
+    assert_eq!(nevra.epoch(), None);
+    assert_eq!(nevra.version(), Some("5.1.8"));
+    assert_eq!(nevra.release(), Some("4.el9"));
+    assert_eq!(nevra.arch(), Some("x86_64"));
+    assert_eq!(nevra.to_string(), "bash-5.1.8-4.el9.x86_64");
+}
+
+#[test]
+fn test_parse_partial_labels() {
+    assert_eq!(parts("bash"), ("bash".into(), None, None, None, None));
+    assert_eq!(
+        parts("openssl-libs"),
+        ("openssl-libs".into(), None, None, None, None)
+    );
+    assert_eq!(
+        parts("bash-5.1.8"),
+        ("bash".into(), None, Some("5.1.8".into()), None, None)
+    );
+    assert_eq!(
+        parts("openssl-libs-1:3.0.7-16.el9"),
+        (
+            "openssl-libs".into(),
+            Some(1),
+            Some("3.0.7".into()),
+            Some("16.el9".into()),
+            None
+        )
+    );
+    assert_eq!(
+        parts("tzdata.noarch"),
+        ("tzdata".into(), None, None, None, Some("noarch".into()))
+    );
+
+    let nevra = Nevra::parse("openssl-libs-1:3.0.7-16.el9").unwrap();
+    assert_eq!(nevra.to_string(), "openssl-libs-1:3.0.7-16.el9");
+}
+
+#[test]
+fn test_parse_file_names() {
+    assert_eq!(
+        parts("bash-5.1.8-4.el9.x86_64.rpm"),
+        (
+            "bash".into(),
+            None,
+            Some("5.1.8".into()),
+            Some("4.el9".into()),
+            Some("x86_64".into())
+        )
+    );
+    assert_eq!(parts("bash-5.1.8-4.el9.src.rpm").4, Some("src".to_owned()));
+    assert_eq!(
+        parts("bash-5.1.8-4.el9.nosrc.rpm").4,
+        Some("nosrc".to_owned())
+    );
+    assert_eq!(
+        parts("my-pkg-2:1.0-1.fc38.myarch.rpm"),
+        (
+            "my-pkg".into(),
+            Some(2),
+            Some("1.0".into()),
+            Some("1.fc38".into()),
+            Some("myarch".into())
+        )
+    );
+}
+
+#[test]
+fn test_parse_invalid() {
+    for label in &[
+        "",
+        "bash-5.1.8.rpm",
+        "-1.0-1.x86_64.rpm",
+        "bash-x:1.0-1.rpm",
+    ] {
+        let err = label.parse::<Nevra>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{}", label);
+    }
+}
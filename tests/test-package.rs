@@ -0,0 +1,53 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for comparing packages, using the package files in testdata/rpms
+
+use librpm::Package;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::{env, fs, process};
+
+mod common;
+
+fn hash(package: &Package) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    package.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn test_package_equality_ignores_location() {
+    common::configure();
+
+    let rpms = common::get_assets_path().join("rpms");
+    let path = rpms.join("hello-1.0-1.noarch.rpm");
+    let copy = env::temp_dir().join(format!("librpm-equality-{}.rpm", process::id()));
+    fs::copy(&path, &copy).unwrap();
+
+    let package = Package::open(&path).unwrap();
+    let copied = Package::open(&copy).unwrap();
+    fs::remove_file(&copy).unwrap();
+
+    assert_ne!(package.path(), copied.path());
+    assert_eq!(package, copied);
+    assert_eq!(hash(&package), hash(&copied));
+
+    // Other metadata is still compared
+    let other = Package::open(rpms.join("hello-xz-1.0-1.noarch.rpm")).unwrap();
+    assert_ne!(package, other);
+}
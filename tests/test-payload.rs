@@ -0,0 +1,197 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for reading package payloads, using the package files in
+//! testdata/rpms
+
+use librpm::error::ErrorKind;
+use librpm::package::Payload;
+use librpm::Package;
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+mod common;
+
+fn package_path(name: &str) -> PathBuf {
+    common::get_assets_path()
+        .join("rpms")
+        .join(format!("{}-1.0-1.noarch.rpm", name))
+}
+
+#[test]
+fn test_payload_entries() {
+    common::configure();
+
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+
+    for &(name, compressor) in &[
+        ("hello", "gzip"),
+        ("hello-xz", "xz"),
+        ("hello-zstd", "zstd"),
+    ] {
+        let package = Package::open(package_path(name)).unwrap();
+        assert_eq!(package.payload_compressor(), Some(compressor));
+
+        let mut payload = package.payload().unwrap();
+        assert_eq!(payload.compressor(), compressor);
+
+        let mut entries = vec![];
+
+        while let Some(mut entry) = payload.next_entry().unwrap() {
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents).unwrap();
+
+            let metadata = entry.metadata();
+            assert_eq!(metadata.user(), "root");
+            assert_eq!(metadata.group(), "root");
+            assert_eq!(metadata.mtime(), mtime);
+
+            entries.push((
+                metadata.path().to_owned(),
+                metadata.mode(),
+                metadata.link_target().map(str::to_owned),
+                metadata.nlink(),
+                metadata.has_content(),
+                contents,
+            ));
+        }
+
+        let entry = |path: &str, mode, link: Option<&str>, nlink, has_content, contents: &str| {
+            (
+                path.to_owned(),
+                mode,
+                link.map(str::to_owned),
+                nlink,
+                has_content,
+                contents.to_owned(),
+            )
+        };
+
+        // Symlink targets are only in the metadata, and the contents of a
+        // set of hardlinks are only read with the last of them
+        assert_eq!(
+            entries,
+            [
+                entry(
+                    "/usr/bin/hello",
+                    0o100_755,
+                    None,
+                    1,
+                    true,
+                    "#!/bin/sh\necho 'Hello, world!'\n"
+                ),
+                entry("/usr/bin/hi", 0o120_777, Some("hello"), 1, false, ""),
+                entry(
+                    "/usr/share/doc/hello/README",
+                    0o100_644,
+                    None,
+                    1,
+                    true,
+                    "Says hello.\n"
+                ),
+                entry("/usr/share/hello", 0o040_755, None, 1, false, ""),
+                entry("/usr/share/hello/greeting", 0o100_644, None, 2, false, ""),
+                entry(
+                    "/usr/share/hello/greeting.en",
+                    0o100_644,
+                    None,
+                    2,
+                    true,
+                    "Hello, world!\n"
+                ),
+            ]
+        );
+
+        assert!(payload.next_entry().unwrap().is_none());
+    }
+}
+
+#[test]
+fn test_payload_metadata() {
+    common::configure();
+
+    let mut payload = Payload::open(package_path("hello")).unwrap();
+
+    let entry = payload.next_entry().unwrap().unwrap();
+    let metadata = entry.metadata();
+    assert!(metadata.is_file());
+    assert!(!metadata.is_dir() && !metadata.is_symlink());
+    assert_eq!(metadata.permissions(), 0o755);
+    assert_eq!(metadata.size(), 31);
+
+    let entry = payload.next_entry().unwrap().unwrap();
+    assert!(entry.metadata().is_symlink());
+    assert_eq!(entry.metadata().size(), 5);
+
+    payload.next_entry().unwrap().unwrap();
+    let entry = payload.next_entry().unwrap().unwrap();
+    assert!(entry.metadata().is_dir());
+    assert_eq!(entry.metadata().permissions(), 0o755);
+
+    let first = payload.next_entry().unwrap().unwrap().metadata().clone();
+    let second = payload.next_entry().unwrap().unwrap().metadata().clone();
+    assert_eq!(first.inode(), second.inode());
+    assert_eq!(first.size(), second.size());
+}
+
+#[test]
+fn test_payload_partial_reads() {
+    common::configure();
+
+    let mut payload = Payload::open(package_path("hello-xz")).unwrap();
+
+    // Contents which aren't read are skipped
+    let mut entry = payload.next_entry().unwrap().unwrap();
+    let mut shebang = [0u8; 9];
+    entry.read_exact(&mut shebang).unwrap();
+    assert_eq!(&shebang, b"#!/bin/sh");
+
+    let paths: Vec<String> = std::iter::from_fn(|| {
+        payload
+            .next_entry()
+            .unwrap()
+            .map(|entry| entry.metadata().path().to_owned())
+    })
+    .collect();
+
+    assert_eq!(
+        paths,
+        [
+            "/usr/bin/hi",
+            "/usr/share/doc/hello/README",
+            "/usr/share/hello",
+            "/usr/share/hello/greeting",
+            "/usr/share/hello/greeting.en",
+        ]
+    );
+
+    // The end of the archive is reported again, not an error
+    assert!(payload.next_entry().unwrap().is_none());
+}
+
+#[test]
+fn test_payload_unsupported_compressor() {
+    common::configure();
+
+    let package = Package::open(package_path("bad-compressor")).unwrap();
+    assert_eq!(package.payload_compressor(), Some("ufdio"));
+
+    let err = package.payload().err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::Header);
+    assert!(err.to_string().contains("ufdio"), "{}", err);
+}
@@ -0,0 +1,58 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for reading headers of installed packages again after the database
+//! changed. This switches the database path, so it runs on its own.
+
+use librpm::error::ErrorKind;
+use librpm::{config::set_db_path, Index};
+
+mod common;
+
+#[test]
+fn test_header_of_changed_record() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("sqlite")).unwrap();
+
+    let find = |name: &str| Index::Name.find(name).next().unwrap();
+    let basesystem = find("basesystem");
+    let bash = find("bash");
+    let tzdata = find("tzdata");
+
+    for package in &[&basesystem, &bash, &tzdata] {
+        package.changelog().unwrap();
+    }
+
+    // After the update (see `testdata/mksqlite.py`), basesystem is unchanged,
+    // bash was rebuilt under the same NEVRA, and tzdata was removed and its
+    // record number reused by openssl-libs
+    set_db_path(&common::get_assets_path().join("sqlite-updated")).unwrap();
+
+    assert_eq!(find("openssl-libs").record(), tzdata.record());
+    assert_eq!(find("bash").nevra(), bash.nevra());
+
+    basesystem.changelog().unwrap();
+
+    for package in &[&bash, &tzdata] {
+        let err = package.changelog().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert_eq!(
+            package.scriptlets().unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+    }
+}
@@ -0,0 +1,104 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for rich (boolean) dependencies
+
+use librpm::error::ErrorKind;
+use librpm::package::{Dependency, RichDep};
+
+mod common;
+
+fn provides(deps: &[&str]) -> Vec<Dependency> {
+    deps.iter().map(|d| Dependency::parse(d).unwrap()).collect()
+}
+
+#[test]
+fn test_parse_rich_dependencies() {
+    common::configure();
+
+    let dep = RichDep::parse("(foo >= 1.0 if bar)").unwrap();
+
+    match &dep {
+        RichDep::If {
+            then,
+            condition,
+            otherwise,
+        } => {
+            assert_eq!(**then, RichDep::Single("foo >= 1.0".parse().unwrap()));
+            assert_eq!(**condition, RichDep::Single("bar".parse().unwrap()));
+            assert!(otherwise.is_none());
+        }
+        other => panic!("unexpected dependency: {:?}", other),
+    }
+
+    for canonical in &[
+        "(foo >= 1.0 if bar)",
+        "(a or b or c)",
+        "(a and (b or c))",
+        "(a with b)",
+        "(a without b)",
+        "(a if b else c)",
+        "(a unless b else (c and d))",
+        "(a unless b)",
+    ] {
+        assert_eq!(RichDep::parse(canonical).unwrap().to_string(), *canonical);
+    }
+
+    assert_eq!(
+        RichDep::parse("glibc >= 2.17").unwrap(),
+        RichDep::Single(Dependency::parse("glibc >= 2.17").unwrap())
+    );
+}
+
+#[test]
+fn test_parse_invalid_rich_dependencies() {
+    common::configure();
+
+    for dep in &["(a or", "(a or b and c)", "(a if)", "(a) b", ""] {
+        let err = RichDep::parse(dep).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{}", dep);
+    }
+}
+
+#[test]
+fn test_evaluate_rich_dependencies() {
+    common::configure();
+
+    let dep = RichDep::parse("(foo >= 1.0 if bar)").unwrap();
+    assert!(dep.is_satisfied_by(&provides(&[])).unwrap());
+    assert!(!dep.is_satisfied_by(&provides(&["bar"])).unwrap());
+    assert!(!dep
+        .is_satisfied_by(&provides(&["bar", "foo = 0.9"]))
+        .unwrap());
+    assert!(dep
+        .is_satisfied_by(&provides(&["bar", "foo = 1.2"]))
+        .unwrap());
+
+    let dep = RichDep::parse("(a or (b and c))").unwrap();
+    assert!(dep.is_satisfied_by(&provides(&["a"])).unwrap());
+    assert!(!dep.is_satisfied_by(&provides(&["b"])).unwrap());
+    assert!(dep.is_satisfied_by(&provides(&["b", "c"])).unwrap());
+
+    let dep = RichDep::parse("(a unless b else c)").unwrap();
+    assert!(dep.is_satisfied_by(&provides(&["a"])).unwrap());
+    assert!(!dep.is_satisfied_by(&provides(&["a", "b"])).unwrap());
+    assert!(dep.is_satisfied_by(&provides(&["b", "c"])).unwrap());
+
+    let dep = RichDep::parse("(a without b)").unwrap();
+    assert!(dep.is_satisfied_by(&provides(&["a"])).unwrap());
+    assert!(!dep.is_satisfied_by(&provides(&["a", "b"])).unwrap());
+}
@@ -0,0 +1,217 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for reading a database using the SQLite backend
+
+use librpm::graph::DependencyGraph;
+use librpm::header::Tag;
+use librpm::package::Dependency;
+use librpm::{config::set_db_path, db, db::installed_packages, Index, Package};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+mod common;
+
+#[test]
+fn test_sqlite_rpm_database() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("sqlite")).unwrap();
+
+    let mut packages: Vec<Package> = installed_packages().collect();
+    packages.sort_by_key(|p| p.name().to_string());
+
+    let names: Vec<&str> = packages.iter().map(|p| p.name()).collect();
+    assert_eq!(names, ["basesystem", "bash", "openssl-libs", "tzdata"]);
+
+    let bash = &packages[1];
+    assert_eq!(bash.epoch(), None);
+    assert_eq!(bash.version(), "5.1.8");
+    assert_eq!(bash.release(), "6.el9");
+    assert_eq!(bash.arch(), Some("x86_64"));
+    assert_eq!(bash.license(), "GPLv3+");
+    assert_eq!(bash.summary(), "The GNU Bourne Again shell");
+
+    assert_eq!(bash.os(), Some("linux"));
+    assert_eq!(bash.url(), None);
+    assert_eq!(bash.sourcerpm(), None);
+    assert_eq!(
+        bash.installtime(),
+        Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+    );
+
+    assert_eq!(packages[2].epoch(), Some(1));
+}
+
+#[test]
+fn test_sqlite_find() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("sqlite")).unwrap();
+
+    let package = Index::Name.find("openssl-libs").next().unwrap();
+    assert_eq!(package.nevra(), "openssl-libs-1:3.0.7-16.el9.x86_64");
+    assert!(Index::Name.find("openssl").next().is_none());
+
+    let record = package.record().unwrap();
+    assert_eq!(db::find_by_record(record), Some(package));
+}
+
+#[test]
+fn test_sqlite_indexes() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("sqlite")).unwrap();
+
+    let names = |packages: Vec<Package>| -> Vec<String> {
+        packages.iter().map(|p| p.name().to_owned()).collect()
+    };
+
+    assert_eq!(names(db::what_provides("/bin/sh").unwrap()), ["bash"]);
+    assert_eq!(names(db::what_provides("/usr/bin/sh").unwrap()), ["bash"]);
+    assert_eq!(
+        names(db::what_provides("libssl.so.3()(64bit)").unwrap()),
+        ["openssl-libs"]
+    );
+    assert_eq!(
+        names(db::what_provides("openssl-libs >= 1:3").unwrap()),
+        ["openssl-libs"]
+    );
+    assert_eq!(
+        names(db::what_requires("/usr/bin/sh").unwrap()),
+        ["openssl-libs"]
+    );
+
+    // Headers carry their digests, as in a database written by rpm
+    for package in installed_packages() {
+        let sha1 = package.sha1header().unwrap();
+        assert_eq!(package.hdrid().unwrap().len(), 64);
+        assert_eq!(db::find_by_hdrid(sha1).as_ref(), Some(&package));
+        assert_eq!(
+            db::find_by_hdrid(package.hdrid().unwrap()).as_ref(),
+            Some(&package)
+        );
+    }
+
+    // Neither a SHA1 nor a SHA256 digest
+    assert!(db::find_by_hdrid(&"0".repeat(50)).is_none());
+}
+
+#[test]
+fn test_sqlite_dependency_graph() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("sqlite")).unwrap();
+
+    let graph = DependencyGraph::installed();
+    let find = |name: &str| graph.packages().iter().find(|p| p.name() == name).unwrap();
+    let names = |packages: Vec<&Package>| -> Vec<String> {
+        packages.iter().map(|p| p.name().to_owned()).collect()
+    };
+
+    // Only the packages a rich dependency may require are edges, not those
+    // in its condition or exclusion:
+    //   basesystem: (bash if openssl-libs)
+    //   bash: (tzdata if basesystem else openssl-libs)
+    //   openssl-libs: /usr/bin/sh
+    //   tzdata: (bash without openssl-libs)
+    assert_eq!(names(graph.requires(find("basesystem"))), ["bash"]);
+    assert_eq!(
+        names(graph.requires(find("bash"))),
+        ["openssl-libs", "tzdata"]
+    );
+    assert_eq!(names(graph.requires(find("openssl-libs"))), ["bash"]);
+    assert_eq!(names(graph.requires(find("tzdata"))), ["bash"]);
+    assert_eq!(
+        names(graph.required_by(find("bash"))),
+        ["basesystem", "openssl-libs", "tzdata"]
+    );
+    assert_eq!(names(graph.leaves()), ["basesystem"]);
+
+    let components = graph.strongly_connected_components();
+    let sizes: Vec<usize> = components.iter().map(Vec::len).collect();
+    assert_eq!(sizes, [3, 1]);
+    assert_eq!(
+        names(components[0].clone()),
+        ["bash", "openssl-libs", "tzdata"]
+    );
+}
+
+#[test]
+fn test_sqlite_requirement_matches_package() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("sqlite")).unwrap();
+
+    let bash = Index::Name.find("bash").next().unwrap();
+    let openssl = Index::Name.find("openssl-libs").next().unwrap();
+
+    let satisfied = |dep: &str, package: &Package| {
+        Dependency::parse(dep)
+            .unwrap()
+            .is_satisfied_by(package)
+            .unwrap()
+    };
+
+    assert!(satisfied("bash", &bash));
+    assert!(satisfied("bash >= 5", &bash));
+    assert!(satisfied("bash = 5.1.8", &bash));
+    assert!(satisfied("bash = 5.1.8-6.el9", &bash));
+    assert!(!satisfied("bash = 5.1.8-7.el9", &bash));
+    assert!(!satisfied("bash < 5", &bash));
+    assert!(!satisfied("tzdata", &bash));
+
+    // openssl-libs has epoch 1, which beats any version without one
+    assert!(satisfied("openssl-libs >= 3.1", &openssl));
+    assert!(!satisfied("openssl-libs <= 3.1", &openssl));
+    assert!(satisfied("openssl-libs <= 1:3.1", &openssl));
+}
+
+#[test]
+fn test_sqlite_iterate_from_threads() {
+    common::configure();
+    set_db_path(&common::get_assets_path().join("sqlite")).unwrap();
+
+    let hello = common::get_assets_path().join("rpms/hello-1.0-1.noarch.rpm");
+
+    // Iterators on different threads take turns, while queries nested in an
+    // iteration on the same thread go ahead, as do package files read with
+    // the global transaction set
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let hello = hello.clone();
+
+            thread::spawn(move || {
+                let mut count = 0;
+
+                for _ in 0..10 {
+                    for package in installed_packages() {
+                        let found = Index::Name.find(package.name()).next();
+                        assert_eq!(found.as_ref(), Some(&package));
+
+                        let file = Package::open(&hello).unwrap();
+                        assert_eq!(file.name(), "hello");
+                        assert!(file.header().unwrap().contains(Tag::PAYLOADCOMPRESSOR));
+                        assert!(package.header().unwrap().contains(Tag::NAME));
+                        count += 1;
+                    }
+                }
+
+                count
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), 40);
+    }
+}
@@ -0,0 +1,146 @@
+/*
+ * Copyright (C) RustRPM Developers
+ *
+ * Licensed under the Mozilla Public License Version 2.0
+ * Fedora-License-Identifier: MPLv2.0
+ * SPDX-2.0-License-Identifier: MPL-2.0
+ * SPDX-3.0-License-Identifier: MPL-2.0
+ *
+ * This is free software.
+ * For more information on the license, see LICENSE.
+ * For more information on free software, see <https://www.gnu.org/philosophy/free-sw.en.html>.
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at <https://mozilla.org/MPL/2.0/>.
+ */
+
+//! Tests for watching the database for changes, by copying fixtures into a
+//! temporary database directory
+
+use librpm::db::{self, Event};
+use librpm::{config::set_db_path, error::ErrorKind};
+use std::time::Duration;
+use std::{env, fs, process};
+
+mod common;
+
+const DB_FILE: &str = "rpmdb.sqlite";
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn fixture(name: &str) -> Vec<u8> {
+    fs::read(common::get_assets_path().join(name).join(DB_FILE)).unwrap()
+}
+
+fn describe(event: Event) -> String {
+    match event {
+        Event::Added(package) => format!("added {}", package),
+        Event::Removed(package) => format!("removed {}", package),
+        Event::Changed => "changed".to_owned(),
+    }
+}
+
+// The configured database is global, so everything is tested in sequence
+#[test]
+fn test_watch() {
+    common::configure();
+
+    let dir = env::temp_dir().join(format!("librpm-watch-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(DB_FILE), fixture("sqlite")).unwrap();
+    set_db_path(&dir).unwrap();
+
+    let mut watcher = db::watch().unwrap();
+    assert_eq!(watcher.dir(), dir);
+
+    // Reading the database doesn't count as a change
+    assert_eq!(db::installed_packages().count(), 4);
+    assert_eq!(
+        watcher.wait_timeout(Duration::from_millis(300)).unwrap(),
+        None
+    );
+
+    fs::write(dir.join(DB_FILE), fixture("sqlite-updated")).unwrap();
+
+    let events: Vec<Event> = (0..6)
+        .map(|_| watcher.wait_timeout(TIMEOUT).unwrap().unwrap())
+        .collect();
+
+    // Packages are told apart by header ID, which changed with the rebuild
+    let bash_ids: Vec<String> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Added(package) | Event::Removed(package) if package.name() == "bash" => {
+                package.hdrid().map(str::to_owned)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(bash_ids.len(), 2);
+    assert_eq!(bash_ids[0].len(), 64);
+    assert_ne!(bash_ids[0], bash_ids[1]);
+
+    assert_eq!(
+        events.into_iter().map(describe).collect::<Vec<_>>(),
+        [
+            // bash was rebuilt, so its header changed
+            "removed bash-5.1.8-6.el9.x86_64",
+            "removed openssl-libs-1:3.0.7-16.el9.x86_64",
+            "removed tzdata-2023c-1.el9.noarch",
+            "added bash-5.1.8-6.el9.x86_64",
+            "added openssl-libs-1:3.0.7-18.el9.i686",
+            "added openssl-libs-1:3.0.7-18.el9.x86_64",
+        ]
+    );
+    assert_eq!(
+        watcher.wait_timeout(Duration::from_millis(300)).unwrap(),
+        None
+    );
+
+    // Rewriting the same packages changes nothing
+    fs::write(dir.join(DB_FILE), fixture("sqlite-updated")).unwrap();
+    assert_eq!(watcher.wait_timeout(TIMEOUT).unwrap(), Some(Event::Changed));
+
+    // rpm closes its transaction lock at the end of each transaction
+    fs::write(dir.join(".rpm.lock"), "").unwrap();
+    assert_eq!(watcher.wait_timeout(TIMEOUT).unwrap(), Some(Event::Changed));
+
+    // Replacing the directory, as `rpmdb --rebuilddb` does, keeps the new
+    // one watched
+    let new_dir = dir.with_extension("new");
+    let old_dir = dir.with_extension("old");
+    fs::create_dir_all(&new_dir).unwrap();
+    fs::write(new_dir.join(DB_FILE), fixture("sqlite")).unwrap();
+    fs::rename(&dir, &old_dir).unwrap();
+    fs::rename(&new_dir, &dir).unwrap();
+    fs::remove_dir_all(&old_dir).unwrap();
+
+    let events: Vec<String> = (0..6)
+        .map(|_| describe(watcher.wait_timeout(TIMEOUT).unwrap().unwrap()))
+        .collect();
+    assert_eq!(
+        events,
+        [
+            "removed bash-5.1.8-6.el9.x86_64",
+            "removed openssl-libs-1:3.0.7-18.el9.i686",
+            "removed openssl-libs-1:3.0.7-18.el9.x86_64",
+            "added bash-5.1.8-6.el9.x86_64",
+            "added openssl-libs-1:3.0.7-16.el9.x86_64",
+            "added tzdata-2023c-1.el9.noarch",
+        ]
+    );
+
+    fs::write(dir.join(DB_FILE), fixture("sqlite")).unwrap();
+    assert_eq!(watcher.wait_timeout(TIMEOUT).unwrap(), Some(Event::Changed));
+
+    // Once the directory is gone, there's nothing left to watch
+    fs::remove_dir_all(&dir).unwrap();
+    let err = watcher.wait_timeout(TIMEOUT).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Io);
+    drop(watcher);
+
+    set_db_path(&dir.join("nonexistent")).unwrap();
+    let err = db::watch().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Io);
+}